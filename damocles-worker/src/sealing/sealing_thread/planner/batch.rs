@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use vc_processors::fil_proofs::{
     to_prover_id, SealCommitPhase1Output, SealCommitPhase2Output, SealPreCommitPhase1Output, SealPreCommitPhase2Output, SectorId,
 };
@@ -7,7 +10,7 @@ use vc_processors::fil_proofs::{
 use anyhow::{anyhow, Context, Result};
 
 use crate::{
-    metadb::{rocks::RocksMeta, MaybeDirty, MetaDocumentDB, PrefixedMetaDB, Saved},
+    metadb::{rocks::RocksMeta, MaybeDirty, PrefixedMetaDB, Saved},
     rpc::sealer::{
         AcquireDealsSpec, AllocateSectorSpec, AllocatedSector, Deals, OnChainState, PieceInfo, PreCommitOnChainInfo, ProofOnChainInfo,
         SealerClient, Seed, SubmitResult, Ticket, WorkerIdentifier,
@@ -19,21 +22,122 @@ use crate::{
     store::Store,
 };
 
+use self::cache_lock::CacheLockRegistry;
+use self::chrome_trace::ChromeTracer;
+use self::coordination::{Coordinator, Lease};
+use self::deadlines::{pick_deadline, DeadlineHint, DeadlineSource};
+use self::metrics::{Metrics, WaitReason};
+use self::sector_log::enter_sector_log;
 use self::sectors::{Sector, Sectors};
+use self::watchdog::{Stall, StallObserver};
 
-use super::{common::sector::Trace, JobTrait, PlannerTrait};
+use super::{JobTrait, PlannerTrait};
 
+mod cache_lock;
+mod chrome_trace;
+mod coordination;
+mod deadlines;
+mod metrics;
+mod sector_log;
 mod sectors;
+mod watchdog;
 
 pub(crate) struct Job {
     pub sectors: Saved<Sectors, &'static str, PrefixedMetaDB<&'static RocksMeta>>,
-    _trace: Vec<Trace>,
+
+    /// In-memory tail of the persisted event trace, for quick inspection
+    /// without round-tripping through `trace_meta`.
+    trace: Vec<TraceEntry>,
+    trace_seq: u64,
 
     pub sealing_ctrl: SealingCtrl<'static>,
     store: &'static Store,
     ident: WorkerIdentifier,
 
-    _trace_meta: MetaDocumentDB<PrefixedMetaDB<&'static RocksMeta>>,
+    /// Append-only, persisted log of every applied `Event`, keyed by a
+    /// monotonic sequence number, backing crash recovery and `replay()`.
+    trace_meta: PrefixedMetaDB<&'static RocksMeta>,
+
+    /// Dead-letter store for sectors that were permanently quarantined out of
+    /// the active batch, keyed by sector index, so operators can inspect,
+    /// retry, or discard them without the whole batch having aborted.
+    dead_letters: PrefixedMetaDB<&'static RocksMeta>,
+
+    /// Backoff policy applied to re-submission/re-polling of a sector phase.
+    retry_policy: RetryPolicy,
+
+    /// Number of consecutive failed attempts seen so far, keyed by sector
+    /// index and phase, so repeated failures back off progressively instead
+    /// of looping at a fixed interval.
+    retry_attempts: RwLock<HashMap<(usize, QuarantinePhase), u32>>,
+
+    /// When each currently in-flight polling wait started, keyed by sector
+    /// index and phase, so a stalled wait can be detected and surfaced.
+    poll_started: RwLock<HashMap<(usize, PollPhase), Instant>>,
+
+    /// How long a sector may sit in a single polling phase before the
+    /// watchdog starts warning that it may be wedged. Defaults to 10 minutes.
+    stall_warn_threshold: Duration,
+
+    /// Coordination backend used to fence this batch's sector range against
+    /// other damocles instances sharing the same sector-manager. Defaults to
+    /// `LocalCoordinator`, which never actually contends with anyone.
+    coordinator: Box<dyn Coordinator>,
+    lease_ttl: Duration,
+    batch_lease: RwLock<Option<Lease>>,
+
+    /// Optional deadline-packing support. When `deadline_source` is set and
+    /// `deadline_packing_enabled` is true, a freshly allocated batch is
+    /// assigned to a single least-loaded proving deadline/partition instead
+    /// of scattering across whatever the sector-manager happens to hand
+    /// back; operators who don't want this keep the old behavior by leaving
+    /// it disabled.
+    deadline_source: Option<Box<dyn DeadlineSource>>,
+    deadline_packing_enabled: bool,
+    partition_size: u64,
+    min_time_to_deadline_close: Duration,
+    batch_deadline_hint: RwLock<Option<DeadlineHint>>,
+
+    /// Adaptive interval-polling backoff, and the current interval in
+    /// effect per sector index, used by `check_proof_state` when no
+    /// long-poll source is configured (or it doesn't support long-poll).
+    adaptive_poller: AdaptivePoller,
+    proof_poll_intervals: RwLock<HashMap<usize, Duration>>,
+
+    /// Optional server-side long-poll source for proof-state, preferred
+    /// over interval polling whenever it reports support.
+    long_poll_source: Option<Box<dyn LongPollProofSource>>,
+
+    /// When set, every stage that drives a specific sector also appends its
+    /// tracing output to `<sector_log_dir>/<sector id>.log` via
+    /// `sector_log::enter_sector_log`, in addition to the global logger.
+    sector_log_dir: Option<PathBuf>,
+
+    /// Warning-or-above event counts collected while a sector's log context
+    /// was active, keyed by sector index, surfaced as part of the job
+    /// result once a sector finishes.
+    sector_warnings: RwLock<HashMap<usize, u32>>,
+
+    /// Span-timing instrumentation for sealing stages, exported as Chrome
+    /// Tracing JSON. A no-op when constructed with `sink: None`. `Arc`-wrapped
+    /// so `ChromeTracer::serve` can hand the merged ring buffer to a
+    /// background HTTP thread the same way `Metrics` does.
+    chrome_tracer: Arc<ChromeTracer>,
+
+    /// Persisted record of sectors the stall watchdog found stuck in a
+    /// polling phase longer than its threshold, keyed by sector index.
+    stalled: PrefixedMetaDB<&'static RocksMeta>,
+
+    /// Per-worker sealing-state gauges/histograms and RPC-poll counters,
+    /// served over HTTP in Prometheus text format by whatever sets up
+    /// `metrics.serve(addr)` at process startup.
+    metrics: Arc<Metrics>,
+
+    /// Process-wide per-file lock registry guarding cache/parent-graph
+    /// files, so the finish stage can reclaim a completed sector's
+    /// `cache_dir` without racing a parallel sector still reading shared
+    /// cache data out of it.
+    cache_locks: Arc<CacheLockRegistry>,
 }
 
 impl Job {
@@ -47,6 +151,296 @@ impl Job {
             .get(index)
             .with_context(|| format!("sector index out of bounds: {}", index))
     }
+
+    fn dead_letter_key(index: usize) -> String {
+        format!("quarantine/{}", index)
+    }
+
+    fn dead_letter(&self, record: &QuarantineRecord) {
+        let key = Self::dead_letter_key(record.index);
+        match serde_json::to_vec(record) {
+            Ok(bytes) => {
+                if let Err(e) = self.dead_letters.set(key.as_bytes(), bytes) {
+                    tracing::warn!(index = record.index, err = %e, "failed to persist quarantined sector to dead-letter store");
+                }
+            }
+            Err(e) => tracing::warn!(index = record.index, err = %e, "failed to serialize quarantine record"),
+        }
+    }
+
+    /// Looks up a previously quarantined sector's record. This is the hook a
+    /// higher-level RPC/config surface can use to inspect, retry, or discard
+    /// a dead-lettered sector.
+    pub fn quarantined_sector(&self, index: usize) -> Result<Option<QuarantineRecord>> {
+        match self.dead_letters.get(Self::dead_letter_key(index).as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Discards a dead-lettered sector's record, e.g. once an operator has
+    /// retried it (by re-allocating) or decided to give up on it for good.
+    pub fn discard_quarantine(&self, index: usize) -> Result<()> {
+        self.dead_letters.delete(Self::dead_letter_key(index).as_bytes())
+    }
+
+    /// Whether `index` has already been dead-lettered out of the active
+    /// batch. Downstream per-index stages check this so a sector quarantined
+    /// in an earlier phase (e.g. `PreCommitCheck`) doesn't keep getting
+    /// driven through every later phase as if nothing had happened.
+    fn is_quarantined(&self, index: usize) -> bool {
+        matches!(self.quarantined_sector(index), Ok(Some(_)))
+    }
+
+    /// Records another failed attempt for `(index, phase)` and returns the
+    /// new attempt count (1-based).
+    fn note_retry(&self, index: usize, phase: QuarantinePhase) -> u32 {
+        let mut attempts = self.retry_attempts.write().expect("retry_attempts lock poisoned");
+        let count = attempts.entry((index, phase)).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Clears the attempt counter for `(index, phase)`, e.g. once the phase
+    /// lands successfully.
+    fn clear_retry(&self, index: usize, phase: QuarantinePhase) {
+        self.retry_attempts.write().expect("retry_attempts lock poisoned").remove(&(index, phase));
+    }
+
+    /// Returns how long `(index, phase)` has been waiting, starting the
+    /// clock on first call for that pair and logging a warning (escalating
+    /// to an error the longer it stalls) once the cumulative wait exceeds
+    /// `stall_warn_threshold`.
+    fn note_poll_wait(&self, index: usize, phase: PollPhase) -> Duration {
+        let elapsed = {
+            let mut started = self.poll_started.write().expect("poll_started lock poisoned");
+            *started.entry((index, phase.clone())).or_insert_with(Instant::now)
+        }
+        .elapsed();
+
+        if elapsed >= self.stall_warn_threshold * 3 {
+            tracing::error!(index, phase = ?phase, ?elapsed, "sector badly stalled waiting on RPC, needs operator attention");
+        } else if elapsed >= self.stall_warn_threshold {
+            tracing::warn!(index, phase = ?phase, ?elapsed, "sector has been waiting on RPC longer than expected");
+        }
+
+        elapsed
+    }
+
+    /// Clears the stall clock for `(index, phase)` once the wait is over.
+    fn clear_poll_wait(&self, index: usize, phase: PollPhase) {
+        self.poll_started.write().expect("poll_started lock poisoned").remove(&(index, phase));
+    }
+
+    /// Acquires a coordination lease over `key` (the allocated sector
+    /// range), so another damocles instance sharing the sector-manager
+    /// can't also claim it while this batch is in flight.
+    fn acquire_batch_lease(&self, key: &str) -> Result<()> {
+        let lease = self.coordinator.acquire(key, self.lease_ttl)?;
+        *self.batch_lease.write().expect("batch_lease lock poisoned") = Some(lease);
+        Ok(())
+    }
+
+    /// Renews the held batch lease, if any. A renewal failure means the
+    /// lease may have been lost to another instance, so the caller must
+    /// treat it as fatal to this job rather than keep driving the batch.
+    fn renew_batch_lease(&self) -> Result<()> {
+        let mut guard = self.batch_lease.write().expect("batch_lease lock poisoned");
+        if let Some(lease) = guard.as_mut() {
+            self.coordinator.renew(lease, self.lease_ttl)?;
+        }
+        Ok(())
+    }
+
+    /// If deadline packing is enabled and a deadline source is configured,
+    /// picks the least-loaded eligible deadline/partition and remembers it
+    /// for the batch. Does nothing (leaving any prior scatter behavior
+    /// intact) when packing is disabled or no source is set.
+    fn pack_batch_deadline(&self) -> Result<()> {
+        if !self.deadline_packing_enabled {
+            return Ok(());
+        }
+        let Some(source) = self.deadline_source.as_ref() else {
+            return Ok(());
+        };
+
+        let deadlines = source.deadlines()?;
+        let hint = pick_deadline(&deadlines, self.partition_size, self.min_time_to_deadline_close);
+
+        if let Some(hint) = hint {
+            tracing::debug!(deadline = hint.deadline, partition = hint.partition, "packed batch into deadline");
+        } else {
+            tracing::warn!("no eligible deadline found for batch packing, leaving batch unassigned");
+        }
+
+        *self.batch_deadline_hint.write().expect("batch_deadline_hint lock poisoned") = hint;
+        Ok(())
+    }
+
+    /// The deadline/partition this batch was packed into, if deadline
+    /// packing produced one. Downstream submission code can use this to
+    /// advise the sector-manager which deadline to target.
+    pub fn batch_deadline_hint(&self) -> Option<DeadlineHint> {
+        *self.batch_deadline_hint.read().expect("batch_deadline_hint lock poisoned")
+    }
+
+    fn trace_key(seq: u64) -> String {
+        format!("trace/{}", seq)
+    }
+
+    fn trace_offset_key() -> &'static str {
+        "trace/offset"
+    }
+
+    /// Appends a trace entry for an applied event and persists it, so a
+    /// crashed worker can reconstruct exactly where a batch left off via
+    /// `replay()`.
+    fn record_trace(&mut self, index: Option<usize>, prior: State, next: State) {
+        self.trace_seq += 1;
+        let entry = TraceEntry {
+            seq: self.trace_seq,
+            at_unix_secs: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            index,
+            prior,
+            next,
+        };
+
+        match serde_json::to_vec(&entry) {
+            Ok(bytes) => {
+                if let Err(e) = self.trace_meta.set(Self::trace_key(entry.seq).as_bytes(), bytes) {
+                    tracing::warn!(seq = entry.seq, err = %e, "failed to persist trace entry");
+                }
+            }
+            Err(e) => tracing::warn!(seq = entry.seq, err = %e, "failed to serialize trace entry"),
+        }
+
+        self.trace.push(entry);
+    }
+
+    /// Drops persisted trace entries once a batch has finished, keeping
+    /// only an offset marker so a future `replay()` knows it has nothing
+    /// earlier to fold in. Called once the batch reaches `State::Finished`.
+    fn compact_trace(&mut self) {
+        for entry in self.trace.drain(..) {
+            if let Err(e) = self.trace_meta.delete(Self::trace_key(entry.seq).as_bytes()) {
+                tracing::warn!(seq = entry.seq, err = %e, "failed to remove compacted trace entry");
+            }
+        }
+
+        if let Err(e) = self.trace_meta.set(Self::trace_offset_key().as_bytes(), self.trace_seq.to_be_bytes().to_vec()) {
+            tracing::warn!(err = %e, "failed to persist trace compaction offset");
+        }
+    }
+
+    /// Returns the interval to wait before the next proof-state poll for
+    /// `index`, advancing the adaptive backoff (or starting it at
+    /// `min_interval` the first time this index is polled).
+    fn next_proof_poll_interval(&self, index: usize) -> Duration {
+        let mut intervals = self.proof_poll_intervals.write().expect("proof_poll_intervals lock poisoned");
+        let next = intervals.get(&index).map(|cur| self.adaptive_poller.advance(*cur)).unwrap_or(self.adaptive_poller.min_interval);
+        intervals.insert(index, next);
+        next
+    }
+
+    /// Resets the adaptive interval for `index` back to the minimum, e.g.
+    /// once its state actually changes.
+    fn reset_proof_poll_interval(&self, index: usize) {
+        self.proof_poll_intervals.write().expect("proof_poll_intervals lock poisoned").remove(&index);
+    }
+
+    /// Establishes the per-sector logging context for `index` for the
+    /// duration of the closure `f`, if `sector_log_dir` is configured;
+    /// otherwise just runs `f` with no extra logging fan-out. The warning
+    /// count accumulated while the context was active is folded into
+    /// `sector_warnings` so it's available via `sector_warning_count` once
+    /// the sector is done, without threading a logger handle through `f`.
+    fn with_sector_log<T>(&self, index: usize, sector_id: &str, f: impl FnOnce() -> T) -> T {
+        let Some(dir) = self.sector_log_dir.as_ref() else {
+            return f();
+        };
+
+        let guard = match enter_sector_log(sector_id.to_string(), dir) {
+            Ok(guard) => guard,
+            Err(e) => {
+                tracing::warn!(index, err = %e, "failed to set up per-sector log file, continuing without it");
+                return f();
+            }
+        };
+
+        let result = f();
+        let warnings = guard.finish();
+        *self.sector_warnings.write().expect("sector_warnings lock poisoned").entry(index).or_insert(0) += warnings;
+
+        result
+    }
+
+    /// How many warning-or-above events have been logged for `index` across
+    /// all sector-log-wrapped stages so far.
+    pub fn sector_warning_count(&self, index: usize) -> u32 {
+        self.sector_warnings.read().expect("sector_warnings lock poisoned").get(&index).copied().unwrap_or(0)
+    }
+
+    fn stall_key(index: usize) -> String {
+        format!("stall/{}", index)
+    }
+
+    /// Persists that the watchdog found `index` stuck in `phase` for
+    /// `stalled_for`, so operators have something concrete to inspect
+    /// beyond the single log line the watchdog already emitted.
+    fn mark_stalled(&self, index: usize, phase: &str, stalled_for: Duration) -> Result<()> {
+        let record = StallRecord {
+            index,
+            phase: phase.to_string(),
+            stalled_for_secs: stalled_for.as_secs(),
+        };
+
+        self.stalled.set(Self::stall_key(index).as_bytes(), serde_json::to_vec(&record)?)
+    }
+
+    /// Looks up a previously recorded stall for `index`, if the watchdog
+    /// has flagged one that hasn't been cleared yet.
+    pub fn stalled_sector(&self, index: usize) -> Result<Option<StallRecord>> {
+        match self.stalled.get(Self::stall_key(index).as_bytes())? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Clears a recorded stall, e.g. once an operator has looked into it or
+    /// the sector's phase has since moved on.
+    pub fn clear_stalled(&self, index: usize) -> Result<()> {
+        self.stalled.delete(Self::stall_key(index).as_bytes())
+    }
+
+    /// Reconstructs the sequence of `(index, prior, next)` state transitions
+    /// a batch went through by folding its persisted trace, in sequence
+    /// order starting just after the last compaction offset. This reproduces
+    /// the exact `State` history for crash-recovery and auditing; it does
+    /// not replay the full `Sectors` payload, since that would additionally
+    /// require persisting each `Event`'s domain data (proofs, tickets, deal
+    /// info, ...), which this trace intentionally keeps out of so the
+    /// dead-letter/trace stores stay small.
+    pub fn replay(trace_meta: &PrefixedMetaDB<&'static RocksMeta>) -> Result<Vec<TraceEntry>> {
+        let offset = match trace_meta.get(Self::trace_offset_key().as_bytes())? {
+            Some(bytes) => {
+                let raw: [u8; 8] = bytes.as_slice().try_into().context("corrupt trace offset marker")?;
+                u64::from_be_bytes(raw)
+            }
+            None => 0,
+        };
+
+        let mut entries = Vec::new();
+        let mut seq = offset + 1;
+        while let Some(bytes) = trace_meta.get(Self::trace_key(seq).as_bytes())? {
+            entries.push(serde_json::from_slice(&bytes)?);
+            seq += 1;
+        }
+
+        Ok(entries)
+    }
 }
 
 impl JobTrait for Job {
@@ -56,6 +450,194 @@ impl JobTrait for Job {
     }
 }
 
+impl StallObserver for Job {
+    fn stalls(&self, threshold: Duration) -> Vec<Stall> {
+        let job_id = format!("{:?}", self.ident);
+
+        self.poll_started
+            .read()
+            .expect("poll_started lock poisoned")
+            .iter()
+            .filter(|(_, started)| started.elapsed() >= threshold)
+            .map(|((index, phase), started)| Stall {
+                job_id: job_id.clone(),
+                index: *index,
+                phase_desc: format!("{:?}", phase),
+                stalled_for: started.elapsed(),
+            })
+            .collect()
+    }
+
+    fn handle_stall(&self, stall: &Stall) -> Result<()> {
+        self.mark_stalled(stall.index, &stall.phase_desc, stall.stalled_for)
+    }
+}
+
+/// A persisted record of a sector the watchdog found stuck in a polling
+/// phase for longer than its stall threshold.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StallRecord {
+    pub index: usize,
+    pub phase: String,
+    pub stalled_for_secs: u64,
+}
+
+/// Identifies which phase a sector was quarantined out of, so that once it's
+/// removed from the active batch `exec()` knows which lane to resume driving
+/// the remaining sectors through.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+pub enum QuarantinePhase {
+    PreCommitSubmit,
+    PreCommitCheck,
+    ProofSubmit,
+    ProofCheck,
+}
+
+/// A persisted record of a sector that was pulled out of the active batch
+/// after hitting a permanent failure, stored in the job's dead-letter store.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuarantineRecord {
+    pub index: usize,
+    pub phase: QuarantinePhase,
+    pub reason: String,
+}
+
+/// Identifies which long-poll loop a sector is currently waiting in, for the
+/// stall watchdog to report on.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PollPhase {
+    PreCommitCheck,
+    ProofCheck,
+    SeedWait,
+}
+
+/// Exponential backoff with jitter for re-submission/re-polling of a sector
+/// phase, so a persistently congested chain/message-pool doesn't get hit
+/// with a thundering herd of resubmissions at a fixed interval.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    pub jitter_fraction: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 8,
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(30 * 60),
+            multiplier: 2.0,
+            jitter_fraction: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Computes the delay to wait before the given (1-based) attempt,
+    /// capped at `max_delay` and perturbed by uniform jitter of
+    /// `±jitter_fraction * delay`.
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let exp = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        let base = (self.base_delay.as_secs_f64() * exp).min(self.max_delay.as_secs_f64());
+
+        let jitter_seed = {
+            use std::collections::hash_map::DefaultHasher;
+            use std::hash::{Hash, Hasher};
+
+            let mut hasher = DefaultHasher::new();
+            Instant::now().hash(&mut hasher);
+            std::thread::current().id().hash(&mut hasher);
+            attempt.hash(&mut hasher);
+            hasher.finish()
+        };
+        // Map the hash to a uniform factor in [-1.0, 1.0].
+        let jitter_unit = (jitter_seed % 2_000_001) as f64 / 1_000_000.0 - 1.0;
+        let jittered = base * (1.0 + self.jitter_fraction * jitter_unit);
+
+        Duration::from_secs_f64(jittered.max(0.0))
+    }
+
+    /// Whether a phase should keep retrying after this many failed attempts.
+    fn should_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+}
+
+/// Adaptive backoff for proof-state interval polling: starts at
+/// `min_interval`, multiplies by `factor` up to `max_interval` on each
+/// "not ready yet" reply, and resets to `min_interval` whenever the polled
+/// state changes. Jitter de-synchronizes many sectors polling at once.
+#[derive(Debug, Clone)]
+pub struct AdaptivePoller {
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    pub factor: f64,
+    pub jitter_fraction: f64,
+}
+
+impl Default for AdaptivePoller {
+    fn default() -> Self {
+        AdaptivePoller {
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(120),
+            factor: 1.5,
+            jitter_fraction: 0.2,
+        }
+    }
+}
+
+impl AdaptivePoller {
+    fn jittered(&self, interval: Duration) -> Duration {
+        let jitter_seed = {
+            use std::collections::hash_map::DefaultHasher;
+            use std::hash::{Hash, Hasher};
+
+            let mut hasher = DefaultHasher::new();
+            Instant::now().hash(&mut hasher);
+            std::thread::current().id().hash(&mut hasher);
+            hasher.finish()
+        };
+        let jitter_unit = (jitter_seed % 2_000_001) as f64 / 1_000_000.0 - 1.0;
+        let jittered = interval.as_secs_f64() * (1.0 + self.jitter_fraction * jitter_unit);
+        Duration::from_secs_f64(jittered.max(0.0))
+    }
+
+    fn advance(&self, current: Duration) -> Duration {
+        let next = current.mul_f64(self.factor).min(self.max_interval).max(self.min_interval);
+        self.jittered(next)
+    }
+}
+
+/// A server-side long-poll source for proof-state, as an alternative to
+/// interval polling: the call blocks on the daemon until either the state
+/// changes or `hold` elapses, cutting out the wasted early round-trips of
+/// interval polling while reacting immediately once the state finally
+/// moves. Falls back to interval polling wherever `supports_long_poll`
+/// returns false (e.g. talking to a daemon that predates this support).
+pub trait LongPollProofSource: Send + Sync {
+    fn supports_long_poll(&self) -> bool;
+
+    /// `last_token` is the state token last observed (`None` on first
+    /// call). Blocks until the state changes or `hold` elapses, returning
+    /// the latest on-chain state, a description, and the token to pass on
+    /// the next call.
+    fn poll_long(&self, sector_key: &str, last_token: Option<String>, hold: Duration) -> Result<(OnChainState, String, String)>;
+}
+
+/// One applied state transition in a batch's persisted event trace: which
+/// sector it affected (if any), and what state it moved from/to.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceEntry {
+    pub seq: u64,
+    pub at_unix_secs: u64,
+    pub index: Option<usize>,
+    pub prior: State,
+    pub next: State,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
 pub enum State {
     Empty,
@@ -75,6 +657,9 @@ pub enum State {
     C2Done { index: usize },
     ProofSubmitted { index: usize },
     Finished { index: usize },
+    // One sector permanently failed and was removed from the active batch;
+    // `phase` says which lane the remaining sectors should resume in.
+    SectorQuarantined { index: usize, phase: QuarantinePhase },
     Aborted,
 }
 
@@ -101,18 +686,43 @@ pub enum Event {
     SubmitProof { index: usize },
     ReSubmitProof { index: usize },
     Finish { index: usize },
+    Quarantine { index: usize, phase: QuarantinePhase, reason: String },
+}
+
+/// The sector index an event is about, if it's about one in particular
+/// (allocation/idle/batch-wide phase-transition events aren't).
+fn event_sector_index(event: &Event) -> Option<usize> {
+    match event {
+        Event::SetState(_) | Event::Idle | Event::Allocate(_) | Event::AssignTicket(_) | Event::PC1(..) | Event::PC2(_) | Event::C1(_) => {
+            None
+        }
+        Event::AcquireDeals { index, .. }
+        | Event::AddPiece { index, .. }
+        | Event::BuildTreeD { index }
+        | Event::SubmitPC { index }
+        | Event::ReSubmitPC { index }
+        | Event::CheckPC { index }
+        | Event::Persist { index, .. }
+        | Event::SubmitPersistance { index }
+        | Event::AssignSeed { index, .. }
+        | Event::C2 { index, .. }
+        | Event::SubmitProof { index }
+        | Event::ReSubmitProof { index }
+        | Event::Finish { index }
+        | Event::Quarantine { index, .. } => Some(*index),
+    }
 }
 
 impl Event {
-    fn apply(self, state: State, job: &mut Job) -> Result<()> {
-        let next = if let Event::SetState(s) = &self { s.clone() } else { state };
+    fn apply(self, next: State, job: &mut Job) -> Result<()> {
+        let next = if let Event::SetState(s) = &self { s.clone() } else { next };
 
         if next == job.sectors.state {
             return Err(anyhow!("state unchanged, may enter an infinite loop"));
         }
 
         self.apply_changes(job.sectors.inner_mut());
-        // task.sector.update_state(next);
+        job.sectors.inner_mut().state = next;
 
         Ok(())
     }
@@ -278,6 +888,17 @@ impl PlannerTrait for BatchPlanner {
             (State::ProofSubmitted { .. }, Event::Finish { index }) | (State::Finished { .. }, Event::Finish { index }) => {
                 State::Finished { index: *index }
             }
+            (_, Event::Quarantine { index, phase, .. }) => State::SectorQuarantined {
+                index: *index,
+                phase: phase.clone(),
+            },
+            // After a quarantine, the next sector's event continues the lane
+            // that `exec` resumed.
+            (State::SectorQuarantined { .. }, Event::SubmitPC { index }) => State::PCSubmitted { index: *index },
+            (State::SectorQuarantined { .. }, Event::CheckPC { index }) => State::PCLanded { index: *index },
+            (State::SectorQuarantined { .. }, Event::Persist { index, .. }) => State::Persisted { index: *index },
+            (State::SectorQuarantined { .. }, Event::SubmitProof { index }) => State::ProofSubmitted { index: *index },
+            (State::SectorQuarantined { .. }, Event::Finish { index }) => State::Finished { index: *index },
             _ => {
                 return Err(anyhow::anyhow!("unexpected state and event {:?} {:?}", st, evt));
             }
@@ -285,6 +906,10 @@ impl PlannerTrait for BatchPlanner {
     }
 
     fn exec(&self, job: &mut Self::Job) -> Result<Option<Self::Event>, Failure> {
+        // Losing the batch lease means another instance may now consider
+        // these sectors its own, so we must not keep driving them forward.
+        job.renew_batch_lease().abort()?;
+
         let state = job.sectors.state.clone();
         let batch_size = job.sectors.batch_size;
 
@@ -318,13 +943,60 @@ impl PlannerTrait for BatchPlanner {
             State::ProofSubmitted { .. } => inner.check_proof_state(0),
             State::Finished { index } if index < batch_size - 1 => inner.check_proof_state(index + 1),
             State::Finished { .. } => return Ok(None),
+            // A sector was quarantined out of the batch; resume driving the
+            // rest of the sectors through the same lane it dropped out of.
+            State::SectorQuarantined {
+                index,
+                phase: QuarantinePhase::PreCommitSubmit,
+            } if index < batch_size - 1 => inner.submit_pre_commit(index + 1),
+            State::SectorQuarantined {
+                phase: QuarantinePhase::PreCommitSubmit,
+                ..
+            } => inner.check_pre_commit_state(0),
+            State::SectorQuarantined {
+                index,
+                phase: QuarantinePhase::PreCommitCheck,
+            } if index < batch_size - 1 => inner.check_pre_commit_state(index + 1),
+            State::SectorQuarantined {
+                phase: QuarantinePhase::PreCommitCheck,
+                ..
+            } => inner.persist_sector_files(0),
+            State::SectorQuarantined {
+                index,
+                phase: QuarantinePhase::ProofSubmit,
+            } if index < batch_size - 1 => inner.submit_proof(index + 1),
+            State::SectorQuarantined {
+                phase: QuarantinePhase::ProofSubmit,
+                ..
+            } => inner.check_proof_state(0),
+            State::SectorQuarantined {
+                index,
+                phase: QuarantinePhase::ProofCheck,
+            } if index < batch_size - 1 => inner.check_proof_state(index + 1),
+            State::SectorQuarantined {
+                phase: QuarantinePhase::ProofCheck,
+                ..
+            } => return Ok(None),
             State::Aborted => return Err(TaskAborted.into()),
         }
         .map(Some)
     }
 
     fn apply(&self, event: Self::Event, state: Self::State, job: &mut Self::Job) -> Result<()> {
-        todo!()
+        let next = self.plan(&event, &state)?;
+        let index = event_sector_index(&event);
+
+        event.apply(next.clone(), job)?;
+        job.record_trace(index, state, next.clone());
+
+        // Only compact once the whole batch has finished, not just the first
+        // sector to reach State::Finished -- with batch_size > 1 the other
+        // sectors are still mid-flight and still need their trace history.
+        if matches!(next, State::Finished { index } if index == job.sectors.batch_size - 1) {
+            job.compact_trace();
+        }
+
+        Ok(())
     }
 }
 
@@ -333,66 +1005,117 @@ struct BatchSealer<'a> {
 }
 
 impl BatchSealer<'_> {
+    /// Wraps `f` in a Chrome Tracing span named `name`, tagged with `index`'s
+    /// sector id and proof type when one is known. A no-op wrapper when no
+    /// trace sink is configured on the job.
+    fn traced<T>(&self, name: &str, index: Option<usize>, f: impl FnOnce() -> T) -> T {
+        let (sector_id, proof_type) = index
+            .and_then(|i| self.job.sector(i).ok())
+            .and_then(|s| s.base.as_ref())
+            .map(|b| (format!("{:?}", b.allocated.id), format!("{:?}", b.allocated.proof_type)))
+            .unwrap_or_default();
+
+        self.job.chrome_tracer.span(name, index, &sector_id, &proof_type, f)
+    }
+
+    /// Waits for `delay`, recording the time spent blocked against `index`'s
+    /// trace accumulator so it can be told apart from time spent doing
+    /// actual work.
+    fn traced_wait(&self, index: usize, delay: Duration) -> Result<(), Failure> {
+        let start = Instant::now();
+        let result = self.job.sealing_ctrl.wait_or_interrupted(delay);
+        self.job.chrome_tracer.record_blocked(index, start.elapsed());
+        result
+    }
+
+    /// Pulls a permanently-failed sector out of the active batch instead of
+    /// aborting it outright, recording why in the job's dead-letter store so
+    /// operators can inspect or retry it later.
+    fn quarantine(&self, index: usize, phase: QuarantinePhase, reason: String) -> Result<Event, Failure> {
+        tracing::warn!(index, phase = ?phase, %reason, "quarantining sector, batch will continue without it");
+        self.job.dead_letter(&QuarantineRecord {
+            index,
+            phase: phase.clone(),
+            reason: reason.clone(),
+        });
+        // The sector is leaving the active batch for this lane; stop tracking
+        // its stall clock so it doesn't linger and trip the watchdog forever.
+        self.job.clear_poll_wait(index, PollPhase::PreCommitCheck);
+        self.job.clear_poll_wait(index, PollPhase::ProofCheck);
+        Ok(Event::Quarantine { index, phase, reason })
+    }
+
     pub fn allocate(&self) -> Result<Event, Failure> {
-        let maybe_allocated_res = call_rpc! {
-            self.job.rpc()=>allocate_sectors_batch(AllocateSectorSpec {
-                allowed_miners: Some(self.job.sealing_ctrl.config().allowed_miners.clone()),
-                allowed_proof_types: Some(self.job.sealing_ctrl.config().allowed_proof_types.clone()),
-                },
-                self.job.sectors.batch_size as u32,
-            )
-        };
+        self.traced("allocate", None, || {
+            let maybe_allocated_res = call_rpc! {
+                self.job.rpc()=>allocate_sectors_batch(AllocateSectorSpec {
+                    allowed_miners: Some(self.job.sealing_ctrl.config().allowed_miners.clone()),
+                    allowed_proof_types: Some(self.job.sealing_ctrl.config().allowed_proof_types.clone()),
+                    },
+                    self.job.sectors.batch_size as u32,
+                )
+            };
 
-        let allocated = match maybe_allocated_res {
-            Ok(a) => a,
-            Err(e) => {
-                tracing::warn!("sectors are not allocated yet, so we can retry even though we got the err {:?}", e);
+            let allocated = match maybe_allocated_res {
+                Ok(a) => a,
+                Err(e) => {
+                    tracing::warn!("sectors are not allocated yet, so we can retry even though we got the err {:?}", e);
+                    return Ok(Event::Idle);
+                }
+            };
+
+            if allocated.is_empty() {
                 return Ok(Event::Idle);
             }
-        };
 
-        if allocated.is_empty() {
-            return Ok(Event::Idle);
-        }
+            let lease_key = allocated.iter().map(|a| format!("{:?}", a.id)).collect::<Vec<_>>().join(",");
+            self.job.acquire_batch_lease(&lease_key).temp()?;
+            self.job.pack_batch_deadline().temp()?;
 
-        Ok(Event::Allocate(allocated))
+            Ok(Event::Allocate(allocated))
+        })
     }
 
     pub fn acquire_deals(&self, index: usize) -> Result<Event, Failure> {
-        let disable_cc = self.job.sealing_ctrl.config().disable_cc;
+        self.traced("acquire_deals", Some(index), || {
+            let disable_cc = self.job.sealing_ctrl.config().disable_cc;
 
-        if !self.job.sealing_ctrl.config().enable_deals {
-            return Ok(if disable_cc {
-                Event::Idle
-            } else {
-                Event::AcquireDeals {
-                    index: self.job.sectors.sectors.len(),
-                    deals: None,
-                }
-            });
-        }
-        let spec = AcquireDealsSpec {
-            max_deals: self.job.sealing_ctrl.config().max_deals,
-            min_used_space: self.job.sealing_ctrl.config().min_deal_space.map(|b| b.get_bytes() as usize),
-        };
+            if !self.job.sealing_ctrl.config().enable_deals {
+                return Ok(if disable_cc {
+                    Event::Idle
+                } else {
+                    Event::AcquireDeals {
+                        index: self.job.sectors.sectors.len(),
+                        deals: None,
+                    }
+                });
+            }
+            let spec = AcquireDealsSpec {
+                max_deals: self.job.sealing_ctrl.config().max_deals,
+                min_used_space: self.job.sealing_ctrl.config().min_deal_space.map(|b| b.get_bytes() as usize),
+            };
 
-        let sector = self.job.sector(index).crit()?;
-        let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+            let sector = self.job.sector(index).crit()?;
+            let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+            let sector_key = format!("{:?}", sector_id);
 
-        let deals = call_rpc! {
-            self.job.rpc()=>acquire_deals(
-                sector_id,
-                spec,
-            )
-        }?;
+            self.job.with_sector_log(index, &sector_key, || -> Result<Event, Failure> {
+                let deals = call_rpc! {
+                    self.job.rpc()=>acquire_deals(
+                        sector_id,
+                        spec,
+                    )
+                }?;
 
-        let deals_count = deals.as_ref().map(|d| d.len()).unwrap_or(0);
+                let deals_count = deals.as_ref().map(|d| d.len()).unwrap_or(0);
 
-        tracing::debug!(count = deals_count, "pieces acquired");
-        Ok(if disable_cc || deals_count > 0 {
-            Event::AcquireDeals { index, deals }
-        } else {
-            Event::Idle
+                tracing::debug!(count = deals_count, "pieces acquired");
+                Ok(if disable_cc || deals_count > 0 {
+                    Event::AcquireDeals { index, deals }
+                } else {
+                    Event::Idle
+                })
+            })
         })
     }
 
@@ -401,115 +1124,181 @@ impl BatchSealer<'_> {
     }
 
     fn build_tree_d(&self) -> Result<Event, Failure> {
-        todo!()
+        // Generates tree_d under the sector's cache_dir, so it takes the
+        // exclusive side of the lock: cleanup must not reclaim this
+        // directory's files while they're mid-generation.
+        let sector = self.job.sector(0).crit()?;
+        let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let cache_dir = self.job.cache_dir(&sector_id);
+
+        self.job.cache_locks.with_write(&cache_dir, || todo!())
     }
 
     fn assign_ticket(&self) -> Result<Event, Failure> {
         let sector = self.job.sector(0).crit()?;
         let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let sector_key = format!("{:?}", sector_id);
+
+        self.job.with_sector_log(0, &sector_key, || self.traced("assign_ticket", Some(0), || {
+            let ticket = match &sector.phases.ticket {
+                // Use the existing ticket when rebuilding sectors
+                Some(ticket) => ticket.clone(),
+                None => {
+                    let ticket = call_rpc! {
+                        self.job.rpc() => assign_ticket(sector_id,)
+                    }?;
+                    tracing::debug!(ticket = ?ticket.ticket.0, epoch = ticket.epoch, "ticket assigned from sector-manager");
+                    ticket
+                }
+            };
 
-        let ticket = match &sector.phases.ticket {
-            // Use the existing ticket when rebuilding sectors
-            Some(ticket) => ticket.clone(),
-            None => {
-                let ticket = call_rpc! {
-                    self.job.rpc() => assign_ticket(sector_id,)
-                }?;
-                tracing::debug!(ticket = ?ticket.ticket.0, epoch = ticket.epoch, "ticket assigned from sector-manager");
-                ticket
-            }
-        };
-
-        Ok(Event::AssignTicket(ticket))
+            Ok(Event::AssignTicket(ticket))
+        }))
     }
 
     fn pc1(&self) -> Result<Event, Failure> {
-        todo!()
+        // pc1 (re)generates the parent-graph cache files under cache_dir, so
+        // it needs the exclusive side of the lock -- the same directory a
+        // parallel sector's finish stage might otherwise try to clean up.
+        let sector = self.job.sector(0).crit()?;
+        let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let cache_dir = self.job.cache_dir(&sector_id);
+
+        self.job.cache_locks.with_write(&cache_dir, || todo!())
     }
 
     fn pc2(&self) -> Result<Event, Failure> {
-        todo!()
+        // pc2 only reads the parent-graph cache pc1 already generated, so it
+        // takes the shared side: other sectors reading the same cached
+        // parent graph can run alongside it, just not cleanup.
+        let sector = self.job.sector(0).crit()?;
+        let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let cache_dir = self.job.cache_dir(&sector_id);
+
+        self.job.cache_locks.with_read(&cache_dir, || todo!())
     }
 
     fn submit_pre_commit(&self, index: usize) -> Result<Event, Failure> {
         let sector = self.job.sector(index).crit()?;
+        let sector_key = format!("{:?}", sector.base.as_ref().map(|b| &b.allocated.id));
 
-        let (sector_id, comm_r, comm_d, ticket) =
-            if let (Some(base), Some(pc2out), Some(ticket)) = (&sector.base, &sector.phases.pc2out, sector.phases.ticket.clone()) {
-                (base.allocated.clone(), pc2out.comm_r, pc2out.comm_d, ticket)
-            } else {
-                return Err(anyhow!("PC2 not completed").crit());
-            };
+        self.job.with_sector_log(index, &sector_key, || self.traced("submit_pre_commit", Some(index), || {
+            let (sector_id, comm_r, comm_d, ticket) =
+                if let (Some(base), Some(pc2out), Some(ticket)) = (&sector.base, &sector.phases.pc2out, sector.phases.ticket.clone()) {
+                    (base.allocated.clone(), pc2out.comm_r, pc2out.comm_d, ticket)
+                } else {
+                    return Err(anyhow!("PC2 not completed").crit());
+                };
 
-        let deals = sector.deals.as_ref().map(|x| x.iter().map(|x| x.id).collect()).unwrap_or_default();
+            let deals = sector.deals.as_ref().map(|x| x.iter().map(|x| x.id).collect()).unwrap_or_default();
 
-        let pinfo = PreCommitOnChainInfo {
-            comm_r,
-            comm_d,
-            ticket,
-            deals,
-        };
+            let pinfo = PreCommitOnChainInfo {
+                comm_r,
+                comm_d,
+                ticket,
+                deals,
+            };
 
-        let res = call_rpc! {
-            self.job.rpc() => submit_pre_commit(sector_id, pinfo, sector.phases.pc2_re_submit,)
-        }?;
+            let res = call_rpc! {
+                self.job.rpc() => submit_pre_commit(sector_id, pinfo, sector.phases.pc2_re_submit,)
+            }?;
 
-        // TODO: handle submit reset correctly
-        match res.res {
-            SubmitResult::Accepted | SubmitResult::DuplicateSubmit => Ok(Event::SubmitPC { index }),
+            // TODO: handle submit reset correctly
+            match res.res {
+                SubmitResult::Accepted | SubmitResult::DuplicateSubmit => Ok(Event::SubmitPC { index }),
 
-            SubmitResult::MismatchedSubmission => Err(anyhow!("{:?}: {:?}", res.res, res.desc).perm()),
+                SubmitResult::MismatchedSubmission => {
+                    self.quarantine(index, QuarantinePhase::PreCommitSubmit, format!("{:?}: {:?}", res.res, res.desc))
+                }
 
-            SubmitResult::Rejected => Err(anyhow!("{:?}: {:?}", res.res, res.desc).abort()),
+                SubmitResult::Rejected => Err(anyhow!("{:?}: {:?}", res.res, res.desc).abort()),
 
-            SubmitResult::FilesMissed => Err(anyhow!("FilesMissed should not happen for pc2 submission: {:?}", res.desc).perm()),
-        }
+                SubmitResult::FilesMissed => Err(anyhow!("FilesMissed should not happen for pc2 submission: {:?}", res.desc).perm()),
+            }
+        }))
     }
 
     fn check_pre_commit_state(&self, index: usize) -> Result<Event, Failure> {
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping pre-commit state check");
+            return Ok(Event::CheckPC { index });
+        }
+
         let sector = self.job.sector(index).crit()?;
         let sector_id = sector.base.as_ref().map(|b| &b.allocated.id).context("context").crit()?;
+        let sector_key = format!("{:?}", sector_id);
 
-        loop {
-            let state = call_rpc! {
-                self.job.rpc()=>poll_pre_commit_state(sector_id.clone(), )
-            }?;
+        self.job.with_sector_log(index, &sector_key, || self.traced("check_pre_commit_state", Some(index), || {
+            // Dropped on every exit path (landed, quarantined, or an early
+            // `?`), so the gauge never leaks even though this loop has
+            // several returns.
+            let _waiting_on_precommit = self.job.metrics.enter_state("waiting_on_precommit_landing");
 
-            match state.state {
-                OnChainState::Landed => break,
-                OnChainState::NotFound => return Err(anyhow!("pre commit on-chain info not found").perm()),
+            loop {
+                self.job.metrics.incr_poll(WaitReason::PreCommitLanding);
 
-                OnChainState::Failed => {
-                    tracing::warn!("pre commit on-chain info failed: {:?}", state.desc);
-                    // TODO: make it configurable
-                    self.job.sealing_ctrl.wait_or_interrupted(Duration::from_secs(30))?;
-                    return Ok(Event::ReSubmitPC { index });
-                }
+                let state = call_rpc! {
+                    self.job.rpc()=>poll_pre_commit_state(sector_id.clone(), )
+                }?;
 
-                OnChainState::PermFailed => return Err(anyhow!("pre commit on-chain info permanent failed: {:?}", state.desc).perm()),
+                match state.state {
+                    OnChainState::Landed => break,
+                    OnChainState::NotFound => return Err(anyhow!("pre commit on-chain info not found").perm()),
 
-                OnChainState::ShouldAbort => return Err(anyhow!("pre commit info will not get on-chain: {:?}", state.desc).abort()),
+                    OnChainState::Failed => {
+                        let attempt = self.job.note_retry(index, QuarantinePhase::PreCommitCheck);
+                        if !self.job.retry_policy.should_retry(attempt) {
+                            return self.quarantine(
+                                index,
+                                QuarantinePhase::PreCommitCheck,
+                                format!("pre commit submission kept failing after {} attempts: {:?}", attempt, state.desc),
+                            );
+                        }
+
+                        let delay = self.job.retry_policy.delay_for(attempt);
+                        tracing::warn!(attempt, ?delay, "pre commit on-chain info failed: {:?}", state.desc);
+                        self.traced_wait(index, delay)?;
+                        return Ok(Event::ReSubmitPC { index });
+                    }
 
-                OnChainState::Pending | OnChainState::Packed => {}
-            }
+                    OnChainState::PermFailed => {
+                        return self.quarantine(
+                            index,
+                            QuarantinePhase::PreCommitCheck,
+                            format!("pre commit on-chain info permanent failed: {:?}", state.desc),
+                        )
+                    }
 
-            tracing::debug!(
-                state = ?state.state,
-                interval = ?self.job.sealing_ctrl.config().rpc_polling_interval,
-                "waiting for next round of polling pre commit state",
-            );
+                    OnChainState::ShouldAbort => return Err(anyhow!("pre commit info will not get on-chain: {:?}", state.desc).abort()),
 
-            self.job
-                .sealing_ctrl
-                .wait_or_interrupted(self.job.sealing_ctrl.config().rpc_polling_interval)?;
-        }
+                    OnChainState::Pending | OnChainState::Packed => {}
+                }
+
+                let waited = self.job.note_poll_wait(index, PollPhase::PreCommitCheck);
+                tracing::debug!(
+                    state = ?state.state,
+                    interval = ?self.job.sealing_ctrl.config().rpc_polling_interval,
+                    total_waited = ?waited,
+                    "waiting for next round of polling pre commit state",
+                );
 
-        tracing::debug!(index = index, "pre commit landed");
+                self.traced_wait(index, self.job.sealing_ctrl.config().rpc_polling_interval)?;
+            }
 
-        Ok(Event::CheckPC { index })
+            self.job.clear_retry(index, QuarantinePhase::PreCommitCheck);
+            self.job.clear_poll_wait(index, PollPhase::PreCommitCheck);
+            tracing::debug!(index = index, "pre commit landed");
+
+            Ok(Event::CheckPC { index })
+        }))
     }
 
     fn persist_sector_files(&self, index: usize) -> Result<Event, Failure> {
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping file persistence");
+            return Ok(Event::Persist { index, instance: String::new() });
+        }
+
         let sector_id = self.job.sector_id()?;
 
         field_required! {
@@ -529,52 +1318,75 @@ impl BatchSealer<'_> {
     }
 
     fn submit_persisted(&self, index: usize) -> Result<Event, Failure> {
-        let sector = self.job.sector(index).crit()?;
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping persisted-file submission");
+            return Ok(Event::SubmitPersistance { index });
+        }
 
+        let sector = self.job.sector(index).crit()?;
         let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
-        let persist_instance = sector
-            .phases
-            .persist_instance
-            .clone()
-            .context("sector persist instance required")
-            .crit()?;
-
-        let checked = call_rpc! {
-            self.job.rpc() => submit_persisted_ex(sector_id.clone(), persist_instance, false,)
-        }?;
+        let sector_key = format!("{:?}", sector_id);
+
+        self.job.with_sector_log(index, &sector_key, || self.traced("submit_persisted", Some(index), || {
+            let persist_instance = sector
+                .phases
+                .persist_instance
+                .clone()
+                .context("sector persist instance required")
+                .crit()?;
+
+            let checked = call_rpc! {
+                self.job.rpc() => submit_persisted_ex(sector_id.clone(), persist_instance, false,)
+            }?;
 
-        if checked {
-            Ok(Event::SubmitPersistance { index })
-        } else {
-            Err(anyhow!("sector files are persisted but unavailable for sealer")).perm()
-        }
+            if checked {
+                Ok(Event::SubmitPersistance { index })
+            } else {
+                Err(anyhow!("sector files are persisted but unavailable for sealer")).perm()
+            }
+        }))
     }
 
     fn wait_seed(&self, index: usize) -> Result<Event, Failure> {
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping seed wait");
+            return Ok(Event::AssignSeed { index, seed: Seed::default() });
+        }
+
         let sector = self.job.sector(index).crit()?;
         let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let sector_key = format!("{:?}", sector_id);
 
-        let seed = loop {
-            let wait = call_rpc! {
-                self.job.rpc()=>wait_seed(sector_id.clone(), )
-            }?;
+        self.job.with_sector_log(index, &sector_key, || self.traced("wait_seed", Some(index), || {
+            let _waiting_on_seed = self.job.metrics.enter_state("waiting_on_seed_assignment");
 
-            if let Some(seed) = wait.seed {
-                break seed;
-            };
+            let seed = loop {
+                self.job.metrics.incr_poll(WaitReason::SeedAssignment);
 
-            if !wait.should_wait || wait.delay == 0 {
-                return Err(anyhow!("invalid empty wait_seed response").temp());
-            }
+                let wait = call_rpc! {
+                    self.job.rpc()=>wait_seed(sector_id.clone(), )
+                }?;
 
-            let delay = Duration::from_secs(wait.delay);
+                if let Some(seed) = wait.seed {
+                    break seed;
+                };
 
-            tracing::debug!(?delay, "waiting for next round of polling seed");
+                if !wait.should_wait || wait.delay == 0 {
+                    return Err(anyhow!("invalid empty wait_seed response").temp());
+                }
 
-            self.job.sealing_ctrl.wait_or_interrupted(delay)?;
-        };
+                let delay = Duration::from_secs(wait.delay);
+
+                let waited = self.job.note_poll_wait(index, PollPhase::SeedWait);
+                tracing::debug!(?delay, total_waited = ?waited, "waiting for next round of polling seed");
+
+                self.traced_wait(index, delay)?;
+            };
 
-        Ok(Event::AssignSeed { index, seed })
+            self.job.clear_poll_wait(index, PollPhase::SeedWait);
+
+            Ok(Event::AssignSeed { index, seed })
+        }))
     }
 
     fn commit1(&self) -> Result<Event, Failure> {
@@ -588,83 +1400,156 @@ impl BatchSealer<'_> {
     }
 
     fn commit2(&self, index: usize) -> Result<Event, Failure> {
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping commit2");
+            return Ok(Event::C2 { index, out: SealCommitPhase2Output::default() });
+        }
         todo!()
     }
 
     fn submit_proof(&self, index: usize) -> Result<Event, Failure> {
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping proof submission");
+            return Ok(Event::SubmitProof { index });
+        }
+
         let sector = self.job.sector(index).crit()?;
         let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let sector_key = format!("{:?}", sector_id);
 
-        let proof = sector.phases.c2out.clone().context("c2out required").crit()?;
+        self.job.with_sector_log(index, &sector_key, || self.traced("submit_proof", Some(index), || {
+            let proof = sector.phases.c2out.clone().context("c2out required").crit()?;
 
-        let info = ProofOnChainInfo { proof: proof.proof.into() };
+            let info = ProofOnChainInfo { proof: proof.proof.into() };
 
-        let res = call_rpc! {
-            self.job.rpc()=>submit_proof(sector_id, info, sector.phases.c2_re_submit,)
-        }?;
+            let res = call_rpc! {
+                self.job.rpc()=>submit_proof(sector_id, info, sector.phases.c2_re_submit,)
+            }?;
 
-        // TODO: submit reset correctly
-        match res.res {
-            SubmitResult::Accepted | SubmitResult::DuplicateSubmit => Ok(Event::SubmitProof { index }),
+            // TODO: submit reset correctly
+            match res.res {
+                SubmitResult::Accepted | SubmitResult::DuplicateSubmit => Ok(Event::SubmitProof { index }),
 
-            SubmitResult::MismatchedSubmission => Err(anyhow!("{:?}: {:?}", res.res, res.desc).perm()),
+                SubmitResult::MismatchedSubmission => self.quarantine(index, QuarantinePhase::ProofSubmit, format!("{:?}: {:?}", res.res, res.desc)),
 
-            SubmitResult::Rejected => Err(anyhow!("{:?}: {:?}", res.res, res.desc).abort()),
+                SubmitResult::Rejected => Err(anyhow!("{:?}: {:?}", res.res, res.desc).abort()),
 
-            SubmitResult::FilesMissed => Err(anyhow!("FilesMissed is not handled currently: {:?}", res.desc).perm()),
-        }
+                SubmitResult::FilesMissed => Err(anyhow!("FilesMissed is not handled currently: {:?}", res.desc).perm()),
+            }
+        }))
     }
 
     fn check_proof_state(&self, index: usize) -> Result<Event, Failure> {
+        if self.job.is_quarantined(index) {
+            tracing::debug!(index, "sector is quarantined, skipping proof-state check");
+            return Ok(Event::Finish { index });
+        }
+
         let sector = self.job.sector(index).crit()?;
         let sector_id = sector.base.as_ref().context("sector base required").crit()?.allocated.id.clone();
+        let sector_key = format!("{:?}", sector_id);
+
+        self.job.with_sector_log(index, &sector_key, || self.traced("check_proof_state", Some(index), || {
+            if !self.job.sealing_ctrl.config().ignore_proof_check {
+                // Held for the whole polling loop below: whichever way the
+                // loop exits (landed, failed permanently, quarantined, or an
+                // early `?` error), dropping this guard decrements the gauge
+                // and records how long the sector sat in this state.
+                let _polling_proof_state = self.job.metrics.enter_state("polling_proof_state");
+
+                let mut long_poll_token: Option<String> = None;
+                let long_polling = self.job.long_poll_source.as_ref().map(|s| s.supports_long_poll()).unwrap_or(false);
+                let mut last_packed: Option<bool> = None;
+
+                loop {
+                    self.job.metrics.incr_poll(WaitReason::ProofLanding);
+
+                    let (chain_state, desc) = if long_polling {
+                        let hold = self.job.adaptive_poller.max_interval;
+                        let (chain_state, desc, token) = self
+                            .job
+                            .long_poll_source
+                            .as_ref()
+                            .expect("long_polling implies a source is set")
+                            .poll_long(&sector_key, long_poll_token.take(), hold)
+                            .temp()?;
+                        long_poll_token = Some(token);
+                        (chain_state, desc)
+                    } else {
+                        let state = call_rpc! {
+                            self.job.rpc() => poll_proof_state(sector_id.clone(),)
+                        }?;
+                        (state.state, format!("{:?}", state.desc))
+                    };
 
-        if !self.job.sealing_ctrl.config().ignore_proof_check {
-            loop {
-                let state = call_rpc! {
-                    self.job.rpc() => poll_proof_state(sector_id.clone(),)
-                }?;
+                    match chain_state {
+                        OnChainState::Landed => break,
+                        OnChainState::NotFound => return Err(anyhow!("proof on-chain info not found").perm()),
+
+                        OnChainState::Failed => {
+                            let attempt = self.job.note_retry(index, QuarantinePhase::ProofCheck);
+                            if !self.job.retry_policy.should_retry(attempt) {
+                                return self.quarantine(
+                                    index,
+                                    QuarantinePhase::ProofCheck,
+                                    format!("proof submission kept failing after {} attempts: {}", attempt, desc),
+                                );
+                            }
+
+                            let delay = self.job.retry_policy.delay_for(attempt);
+                            tracing::warn!(attempt, ?delay, "proof on-chain info failed: {}", desc);
+                            self.traced_wait(index, delay)?;
+                            self.job.reset_proof_poll_interval(index);
+                            return Ok(Event::ReSubmitProof { index });
+                        }
+
+                        OnChainState::PermFailed => {
+                            return self.quarantine(index, QuarantinePhase::ProofCheck, format!("proof on-chain info permanent failed: {}", desc))
+                        }
+
+                        OnChainState::ShouldAbort => return Err(anyhow!("sector will not get on-chain: {}", desc).abort()),
+
+                        OnChainState::Pending | OnChainState::Packed => {
+                            // Moving between Pending and Packed is a state
+                            // change, so the adaptive interval starts over.
+                            let packed = matches!(chain_state, OnChainState::Packed);
+                            if last_packed.is_some_and(|was| was != packed) {
+                                self.job.reset_proof_poll_interval(index);
+                            }
+                            last_packed = Some(packed);
+                        }
+                    }
 
-                match state.state {
-                    OnChainState::Landed => break,
-                    OnChainState::NotFound => return Err(anyhow!("proof on-chain info not found").perm()),
+                    let waited = self.job.note_poll_wait(index, PollPhase::ProofCheck);
 
-                    OnChainState::Failed => {
-                        tracing::warn!("proof on-chain info failed: {:?}", state.desc);
-                        // TODO: make it configurable
-                        self.job.sealing_ctrl.wait_or_interrupted(Duration::from_secs(30))?;
-                        return Ok(Event::ReSubmitProof { index });
+                    if long_polling {
+                        tracing::debug!(total_waited = ?waited, "long-poll returned without a state change, polling again");
+                        continue;
                     }
 
-                    OnChainState::PermFailed => return Err(anyhow!("proof on-chain info permanent failed: {:?}", state.desc).perm()),
+                    let interval = self.job.next_proof_poll_interval(index);
+                    tracing::debug!(?interval, total_waited = ?waited, "waiting for next round of polling proof state");
 
-                    OnChainState::ShouldAbort => return Err(anyhow!("sector will not get on-chain: {:?}", state.desc).abort()),
-
-                    OnChainState::Pending | OnChainState::Packed => {}
+                    self.traced_wait(index, interval)?;
                 }
-
-                tracing::debug!(
-                    state = ?state.state,
-                    interval = ?self.job.sealing_ctrl.config().rpc_polling_interval,
-                    "waiting for next round of polling proof state",
-                );
-
-                self.job
-                    .sealing_ctrl
-                    .wait_or_interrupted(self.job.sealing_ctrl.config().rpc_polling_interval)?;
             }
-        }
-
-        // let cache_dir = self.job.cache_dir(sector_id);
-        // let sector_size = allocated.proof_type.sector_size();
 
-        // we should be careful here, use failure as temporary
-        // clear_cache(sector_size, cache_dir.as_ref()).temp()?;
-        // debug!(
-        //     dir = ?&cache_dir,
-        //     "clean up unnecessary cached files"
-        // );
+            self.job.clear_retry(index, QuarantinePhase::ProofCheck);
+            self.job.clear_poll_wait(index, PollPhase::ProofCheck);
+            self.job.reset_proof_poll_interval(index);
+
+            // Reclaim this sector's cache_dir now that its proof has landed.
+            // Guarded by the exclusive side of the cache lock registry so a
+            // parallel sector still reading shared parent-graph cache data
+            // out of the same directory is skipped rather than corrupted.
+            let cache_dir = self.job.cache_dir(&sector_id);
+            if let Err(e) = self.job.cache_locks.clear_unused(&cache_dir) {
+                tracing::warn!(dir = ?cache_dir, err = %e, "failed to clean up unnecessary cached files");
+            } else {
+                tracing::debug!(dir = ?cache_dir, "cleaned up unnecessary cached files");
+            }
 
-        Ok(Event::Finish { index })
+            Ok(Event::Finish { index })
+        }))
     }
 }
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Process-wide registry of per-file locks, keyed by canonical path, so
+/// concurrent sealing threads can safely share cache/parent-graph files:
+/// a stage takes a shared lock while it reads a file and an exclusive lock
+/// while it generates one, and cleanup takes an exclusive lock before
+/// removing one, so a sector
+/// mid-read never has its cache pulled out from under it by a parallel
+/// sector's cleanup pass.
+pub struct CacheLockRegistry {
+    locks: Mutex<HashMap<PathBuf, Arc<RwLock<()>>>>,
+}
+
+impl CacheLockRegistry {
+    pub fn new() -> Self {
+        CacheLockRegistry { locks: Mutex::new(HashMap::new()) }
+    }
+
+    fn canonical(path: &Path) -> PathBuf {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    fn lock_for(&self, path: &Path) -> Arc<RwLock<()>> {
+        let key = Self::canonical(path);
+        self.locks
+            .lock()
+            .expect("cache lock registry poisoned")
+            .entry(key)
+            .or_insert_with(|| Arc::new(RwLock::new(())))
+            .clone()
+    }
+
+    /// Holds a shared lock on `path` for the duration of `f`. Any number of
+    /// sectors may hold the shared lock at once (e.g. several sectors
+    /// reading the same parent-graph cache file); cleanup's exclusive lock
+    /// can't be taken until all of them release it.
+    pub fn with_read<T>(&self, path: &Path, f: impl FnOnce() -> T) -> T {
+        let lock = self.lock_for(path);
+        let _guard = lock.read().expect("cache file lock poisoned");
+        f()
+    }
+
+    /// Holds an exclusive lock on `path` for the duration of `f`, used when
+    /// a stage (re)generates a cache file in place rather than just reading
+    /// it.
+    pub fn with_write<T>(&self, path: &Path, f: impl FnOnce() -> T) -> T {
+        let lock = self.lock_for(path);
+        let _guard = lock.write().expect("cache file lock poisoned");
+        f()
+    }
+
+    /// Removes every file directly under `cache_dir` that isn't currently
+    /// held (shared or exclusive) by another sector, skipping -- not
+    /// failing -- the ones that are. Meant to be called from the finish
+    /// stage of a completed sector, to reclaim cache space without
+    /// disturbing a parallel sector still using shared parent-graph data.
+    /// If `cache_dir` itself is held (a stage locks the whole directory),
+    /// nothing under it is removed.
+    pub fn clear_unused(&self, cache_dir: &Path) -> io::Result<()> {
+        let dir_lock = self.lock_for(cache_dir);
+        let _dir_guard = match dir_lock.try_write() {
+            Ok(guard) => guard,
+            Err(_) => {
+                tracing::debug!(dir = %cache_dir.display(), "skipping cache dir still in use by another sector");
+                return Ok(());
+            }
+        };
+
+        let entries = match fs::read_dir(cache_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let lock = self.lock_for(&path);
+            match lock.try_write() {
+                Ok(_guard) => {
+                    if let Err(e) = fs::remove_file(&path) {
+                        if e.kind() != io::ErrorKind::NotFound {
+                            tracing::warn!(path = %path.display(), err = %e, "failed to remove cache file during cleanup");
+                        }
+                    }
+                }
+                Err(_) => {
+                    tracing::debug!(path = %path.display(), "skipping cache file still in use by another sector");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    /// Runs parallel generators, readers, and a cleanup pass against the same
+    /// overlapping set of cache files and asserts that no generator or reader
+    /// ever observes a missing-file error, i.e. cleanup never pulls a file
+    /// out from under a sector that's still using it.
+    #[test]
+    fn concurrent_generate_read_cleanup_never_sees_missing_file() {
+        let dir = std::env::temp_dir().join(format!("cache_lock_test_{:?}", std::thread::current().id()));
+        fs::create_dir_all(&dir).expect("create test cache dir");
+
+        let registry = Arc::new(CacheLockRegistry::new());
+        let file_count = 4;
+        let rounds = 20;
+        let barrier = Arc::new(Barrier::new(file_count * 2 + 1));
+
+        let mut handles = Vec::new();
+
+        for i in 0..file_count {
+            let path = dir.join(format!("file-{}", i));
+            fs::write(&path, b"seed").expect("seed cache file");
+
+            // Generator: repeatedly (re)writes the file under an exclusive lock.
+            {
+                let registry = registry.clone();
+                let path = path.clone();
+                let barrier = barrier.clone();
+                handles.push(thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..rounds {
+                        registry.with_write(&path, || {
+                            fs::write(&path, b"generated").expect("generator must not see a missing file");
+                        });
+                    }
+                }));
+            }
+
+            // Reader: repeatedly reads the file under a shared lock.
+            {
+                let registry = registry.clone();
+                let path = path.clone();
+                let barrier = barrier.clone();
+                handles.push(thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..rounds {
+                        registry.with_read(&path, || {
+                            fs::read(&path).expect("reader must not see a missing file");
+                        });
+                    }
+                }));
+            }
+        }
+
+        // Cleanup: repeatedly tries to clear unused files out of the same dir.
+        {
+            let registry = registry.clone();
+            let dir = dir.clone();
+            let barrier = barrier.clone();
+            handles.push(thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..rounds {
+                    registry.clear_unused(&dir).expect("clear_unused should not fail");
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.join().expect("thread panicked");
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// One Chrome Tracing Format "complete" (`ph: "X"`) event, consumable by
+/// chrome://tracing and most flamegraph viewers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChromeTraceEvent {
+    pub name: String,
+    pub ph: &'static str,
+    pub ts: u64,
+    pub dur: u64,
+    pub pid: u32,
+    pub tid: u64,
+    pub args: serde_json::Value,
+}
+
+/// Where completed spans are written: one append-only file per sector, or a
+/// single fixed-capacity ring buffer shared across the whole batch (oldest
+/// spans are dropped once it's full, bounding memory for long-running
+/// batches at the cost of losing the earliest history).
+#[derive(Debug, Clone)]
+pub enum TraceSink {
+    PerSector { dir: PathBuf },
+    Merged { capacity: usize },
+}
+
+/// Optional span-timing instrumentation for sealing stages. Disabled by
+/// default (`sink: None`); when enabled, every stage wrapped with `span`
+/// produces a begin/end-timestamped Chrome Tracing event, and the
+/// blocked-vs-worked time split per sector can be read back with
+/// `time_breakdown` -- giving operators a concrete profile of where sealing
+/// time goes instead of guessing from log timestamps.
+pub struct ChromeTracer {
+    sink: Option<TraceSink>,
+    epoch: Instant,
+    pid: u32,
+    merged: Mutex<VecDeque<ChromeTraceEvent>>,
+    worked: Mutex<HashMap<usize, Duration>>,
+    blocked: Mutex<HashMap<usize, Duration>>,
+}
+
+impl ChromeTracer {
+    pub fn new(sink: Option<TraceSink>) -> Self {
+        ChromeTracer {
+            sink,
+            epoch: Instant::now(),
+            pid: std::process::id(),
+            merged: Mutex::new(VecDeque::new()),
+            worked: Mutex::new(HashMap::new()),
+            blocked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn thread_id(&self) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        std::thread::current().id().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Times `f`, and if a sink is configured, records a Chrome Tracing
+    /// event named `name` tagged with `sector_id`/`proof_type`, folding the
+    /// elapsed time into `index`'s "worked" accumulator.
+    pub fn span<T>(&self, name: &str, index: Option<usize>, sector_id: &str, proof_type: &str, f: impl FnOnce() -> T) -> T {
+        if self.sink.is_none() {
+            return f();
+        }
+
+        let start = Instant::now();
+        let result = f();
+        let dur = start.elapsed();
+
+        if let Some(index) = index {
+            *self.worked.lock().expect("worked lock poisoned").entry(index).or_insert(Duration::ZERO) += dur;
+        }
+
+        self.emit(ChromeTraceEvent {
+            name: name.to_string(),
+            ph: "X",
+            ts: start.saturating_duration_since(self.epoch).as_micros() as u64,
+            dur: dur.as_micros() as u64,
+            pid: self.pid,
+            tid: self.thread_id(),
+            args: serde_json::json!({ "sector_id": sector_id, "proof_type": proof_type }),
+        });
+
+        result
+    }
+
+    /// Records time `index` spent blocked in `wait_or_interrupted` (e.g.
+    /// during proof-state polling), kept apart from span time so it can be
+    /// told apart from time spent doing actual work in `time_breakdown`.
+    pub fn record_blocked(&self, index: usize, dur: Duration) {
+        if self.sink.is_none() {
+            return;
+        }
+        *self.blocked.lock().expect("blocked lock poisoned").entry(index).or_insert(Duration::ZERO) += dur;
+    }
+
+    /// How long `index` has spent doing actual work (inside spans) versus
+    /// blocked waiting, across everything recorded for it so far.
+    pub fn time_breakdown(&self, index: usize) -> (Duration, Duration) {
+        let worked = self.worked.lock().expect("worked lock poisoned").get(&index).copied().unwrap_or_default();
+        let blocked = self.blocked.lock().expect("blocked lock poisoned").get(&index).copied().unwrap_or_default();
+        // Blocked waits happen inside the sector's spans, so span time
+        // includes them; take them out to leave only the working time.
+        (worked.saturating_sub(blocked), blocked)
+    }
+
+    fn emit(&self, event: ChromeTraceEvent) {
+        match &self.sink {
+            None => {}
+            Some(TraceSink::Merged { capacity }) => {
+                let mut buf = self.merged.lock().expect("merged trace lock poisoned");
+                if buf.len() >= *capacity {
+                    buf.pop_front();
+                }
+                buf.push_back(event);
+            }
+            Some(TraceSink::PerSector { dir }) => {
+                if let Err(e) = self.append_per_sector(dir, &event) {
+                    tracing::warn!(err = %e, "failed to append chrome trace event");
+                }
+            }
+        }
+    }
+
+    /// Appends `event` to the per-sector trace file, keeping the whole file a
+    /// single valid JSON array (`[ev1, ev2, ...]`) so it loads directly in
+    /// chrome://tracing instead of just being a sequence of bare objects.
+    /// Each append re-reads and re-writes the file; trace files are small
+    /// enough (one sector's worth of spans) for this to be fine.
+    fn append_per_sector(&self, dir: &Path, event: &ChromeTraceEvent) -> Result<()> {
+        fs::create_dir_all(dir).with_context(|| format!("create trace dir {}", dir.display()))?;
+
+        let sector_id = event.args.get("sector_id").and_then(|v| v.as_str()).unwrap_or("unknown");
+        let path = dir.join(format!("{}.trace.json", sector_id));
+
+        let mut events: Vec<ChromeTraceEvent> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+        events.push(event.clone());
+
+        fs::write(&path, serde_json::to_string(&events)?).with_context(|| format!("write trace file {}", path.display()))
+    }
+
+    /// Snapshot of the merged ring buffer's events, if that sink mode is in
+    /// effect -- e.g. for an RPC endpoint to dump the current trace.
+    pub fn merged_events(&self) -> Vec<ChromeTraceEvent> {
+        self.merged.lock().expect("merged trace lock poisoned").iter().cloned().collect()
+    }
+
+    /// Serves the merged ring buffer's current events as a JSON array over
+    /// plain HTTP at `/trace` on `addr`, in a dedicated background thread,
+    /// mirroring `Metrics::serve`. A no-op consumer when `sink` isn't
+    /// `Merged` -- `merged_events()` just comes back empty.
+    pub fn serve(self: Arc<Self>, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr).with_context(|| format!("bind chrome trace listener on {}", addr))?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let tracer = self.clone();
+                match stream {
+                    Ok(stream) => {
+                        thread::spawn(move || {
+                            if let Err(e) = handle_trace_request(stream, &tracer) {
+                                tracing::warn!(err = %e, "failed to serve a chrome trace request");
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!(err = %e, "failed to accept chrome trace connection"),
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn handle_trace_request(mut stream: std::net::TcpStream, tracer: &ChromeTracer) -> Result<()> {
+    // Same one-fixed-resource simplification as the metrics server: no
+    // request parsing, every connection just gets the current merged trace.
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf);
+
+    let body = serde_json::to_string(&tracer.merged_events()).context("serialize merged trace events")?;
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).context("write chrome trace response")
+}
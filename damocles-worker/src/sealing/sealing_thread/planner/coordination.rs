@@ -0,0 +1,144 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+use crate::metadb::{rocks::RocksMeta, PrefixedMetaDB};
+
+/// A fencing token paired with an expiry, proving exclusive ownership of a
+/// coordination key for as long as it hasn't lapsed. A lease whose holder
+/// lets it expire must be treated as no longer guarding the resource, even
+/// if the process that held it is still running.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    pub key: String,
+    pub fence_token: u64,
+    pub expires_at: Instant,
+}
+
+impl Lease {
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+}
+
+/// Coordinates exclusive ownership of a batch job's sector range across
+/// however many damocles instances are pointed at the same sector-manager,
+/// so `BatchSealer::allocate` can't double-allocate the same sectors.
+pub trait Coordinator: Send + Sync {
+    /// Acquires a lease on `key` for `ttl`, handing back a fence token that
+    /// strictly increases on every successful acquisition.
+    fn acquire(&self, key: &str, ttl: Duration) -> Result<Lease>;
+
+    /// Extends an already-held lease's expiry, failing if it has lapsed and
+    /// been claimed by someone else in the meantime.
+    fn renew(&self, lease: &mut Lease, ttl: Duration) -> Result<()>;
+
+    /// Gives up a held lease early, e.g. once a batch finishes.
+    fn release(&self, lease: Lease) -> Result<()>;
+}
+
+/// Default single-node coordinator backed by the worker's own RocksDB
+/// instance. There's only ever one process touching that database, so this
+/// never actually contends with anyone else -- it exists purely so
+/// `allocate()` has a uniform `Coordinator` to call whether or not a real
+/// distributed backend is configured.
+pub struct LocalCoordinator {
+    meta: PrefixedMetaDB<&'static RocksMeta>,
+}
+
+impl LocalCoordinator {
+    pub fn new(meta: PrefixedMetaDB<&'static RocksMeta>) -> Self {
+        LocalCoordinator { meta }
+    }
+
+    fn fence_key(key: &str) -> String {
+        format!("lease/{}/fence", key)
+    }
+}
+
+impl Coordinator for LocalCoordinator {
+    fn acquire(&self, key: &str, ttl: Duration) -> Result<Lease> {
+        let fence_key = Self::fence_key(key);
+        let next = match self.meta.get(fence_key.as_bytes())? {
+            Some(bytes) => {
+                let raw: [u8; 8] = bytes.as_slice().try_into().map_err(|_| anyhow!("corrupt fence token for {}", key))?;
+                u64::from_be_bytes(raw) + 1
+            }
+            None => 1,
+        };
+        self.meta.set(fence_key.as_bytes(), next.to_be_bytes().to_vec())?;
+
+        Ok(Lease {
+            key: key.to_string(),
+            fence_token: next,
+            expires_at: Instant::now() + ttl,
+        })
+    }
+
+    fn renew(&self, lease: &mut Lease, ttl: Duration) -> Result<()> {
+        // Single-node: nobody else can have taken the fence token out from
+        // under us, so renewal is just pushing the expiry out.
+        lease.expires_at = Instant::now() + ttl;
+        Ok(())
+    }
+
+    fn release(&self, _lease: Lease) -> Result<()> {
+        // Nothing to release locally; the fence counter is left in place so
+        // the next acquisition still hands out a strictly increasing token.
+        Ok(())
+    }
+}
+
+/// The network operations a distributed lock service (e.g. etcd) needs to
+/// support for `DistributedCoordinator` to build leases on top of it. Kept
+/// separate from `Coordinator` so the acquire/keepalive/revoke lifecycle below
+/// is shared by any backend instead of re-implemented per client library.
+pub trait DistributedLeaseBackend: Send + Sync {
+    /// Creates `key` with the given TTL if it doesn't already exist,
+    /// returning the backend-issued fence token. Must fail if `key` is
+    /// already held by someone else.
+    fn put_if_absent(&self, key: &str, ttl: Duration) -> Result<u64>;
+
+    /// Refreshes `key`'s TTL, failing if `fence_token` no longer matches
+    /// what the backend has on record (i.e. the lease was lost).
+    fn keep_alive(&self, key: &str, fence_token: u64, ttl: Duration) -> Result<()>;
+
+    /// Releases `key` early, if `fence_token` still matches.
+    fn revoke(&self, key: &str, fence_token: u64) -> Result<()>;
+}
+
+/// Coordinates leases through an external distributed lock service reached
+/// over `backend`, so multiple damocles instances sharing one
+/// sector-manager can safely split up batch jobs without a central
+/// scheduler. A crashed instance's lease simply expires, letting another
+/// instance re-claim its sectors once the TTL lapses.
+pub struct DistributedCoordinator<B> {
+    backend: B,
+}
+
+impl<B: DistributedLeaseBackend> DistributedCoordinator<B> {
+    pub fn new(backend: B) -> Self {
+        DistributedCoordinator { backend }
+    }
+}
+
+impl<B: DistributedLeaseBackend> Coordinator for DistributedCoordinator<B> {
+    fn acquire(&self, key: &str, ttl: Duration) -> Result<Lease> {
+        let fence_token = self.backend.put_if_absent(key, ttl)?;
+        Ok(Lease {
+            key: key.to_string(),
+            fence_token,
+            expires_at: Instant::now() + ttl,
+        })
+    }
+
+    fn renew(&self, lease: &mut Lease, ttl: Duration) -> Result<()> {
+        self.backend.keep_alive(&lease.key, lease.fence_token, ttl)?;
+        lease.expires_at = Instant::now() + ttl;
+        Ok(())
+    }
+
+    fn release(&self, lease: Lease) -> Result<()> {
+        self.backend.revoke(&lease.key, lease.fence_token)
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+/// A single partition within a proving deadline, as reported by the miner.
+#[derive(Debug, Clone)]
+pub struct PartitionStatus {
+    pub index: u64,
+    pub live_sectors: u64,
+}
+
+/// A miner's proving-deadline occupancy, as needed to decide where a newly
+/// allocated batch should land.
+#[derive(Debug, Clone)]
+pub struct DeadlineStatus {
+    pub index: u64,
+    pub partitions: Vec<PartitionStatus>,
+    /// How long until this deadline's proving window opens.
+    pub close_in: Duration,
+}
+
+impl DeadlineStatus {
+    fn live_sectors(&self) -> u64 {
+        self.partitions.iter().map(|p| p.live_sectors).sum()
+    }
+}
+
+/// Where a newly allocated batch should be assigned to minimize future
+/// WindowPoSt work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineHint {
+    pub deadline: u64,
+    pub partition: u64,
+}
+
+/// Queries a miner's current deadline/partition occupancy. Kept as a trait
+/// so the allocation path doesn't need to know whether the data came from a
+/// live chain query, a cached snapshot, or (in tests) a fixed fixture.
+pub trait DeadlineSource: Send + Sync {
+    fn deadlines(&self) -> Result<Vec<DeadlineStatus>>;
+}
+
+/// Picks the least-loaded eligible deadline and the partition within it that
+/// the batch should land in: among deadlines far enough from closing,
+/// prefer whichever has the fewest live sectors overall, then within that
+/// deadline prefer topping up its fullest partition that still has room
+/// under `partition_size` before opening a new one.
+pub fn pick_deadline(deadlines: &[DeadlineStatus], partition_size: u64, min_time_to_close: Duration) -> Option<DeadlineHint> {
+    let chosen = deadlines
+        .iter()
+        .filter(|d| d.close_in >= min_time_to_close)
+        .min_by_key(|d| d.live_sectors())?;
+
+    let partition = chosen
+        .partitions
+        .iter()
+        .filter(|p| p.live_sectors < partition_size)
+        .max_by_key(|p| p.live_sectors)
+        .map(|p| p.index)
+        .unwrap_or(chosen.partitions.len() as u64);
+
+    Some(DeadlineHint {
+        deadline: chosen.index,
+        partition,
+    })
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+/// Why a sector is currently blocked waiting on something external. Used to
+/// label poll counters so dashboards can show exactly where sealing time is
+/// going instead of a single undifferentiated "waiting" bucket.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WaitReason {
+    Daemon,
+    ChainMessage,
+    PreCommitLanding,
+    SeedAssignment,
+    ProofLanding,
+}
+
+impl WaitReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WaitReason::Daemon => "waiting_on_daemon",
+            WaitReason::ChainMessage => "waiting_on_chain_message",
+            WaitReason::PreCommitLanding => "waiting_on_precommit",
+            WaitReason::SeedAssignment => "waiting_on_seed",
+            WaitReason::ProofLanding => "waiting_on_proof",
+        }
+    }
+}
+
+const DURATION_BUCKETS_SECS: &[f64] = &[1.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0, 21600.0];
+
+/// A fixed-bucket cumulative histogram, accumulated with plain atomics so
+/// it can be shared across sealing threads without pulling in an external
+/// metrics crate.
+struct Histogram {
+    buckets: Vec<AtomicU64>,
+    sum_millis: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            buckets: DURATION_BUCKETS_SECS.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum_millis: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, dur: Duration) {
+        let secs = dur.as_secs_f64();
+        for (bound, bucket) in DURATION_BUCKETS_SECS.iter().zip(&self.buckets) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.sum_millis.fetch_add(dur.as_millis() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, name: &str, state: &str, out: &mut String) {
+        for (bound, bucket) in DURATION_BUCKETS_SECS.iter().zip(&self.buckets) {
+            let _ = writeln!(out, "{}_bucket{{state=\"{}\",le=\"{}\"}} {}", name, state, bound, bucket.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(out, "{}_bucket{{state=\"{}\",le=\"+Inf\"}} {}", name, state, self.count.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{}_sum{{state=\"{}\"}} {}", name, state, self.sum_millis.load(Ordering::Relaxed) as f64 / 1000.0);
+        let _ = writeln!(out, "{}_count{{state=\"{}\"}} {}", name, state, self.count.load(Ordering::Relaxed));
+    }
+}
+
+/// Per-worker metrics registry: how many sectors currently sit in each
+/// sealing state, how long they tend to spend in each one, and how many
+/// RPC polls have been issued for each reason a sector might be blocked.
+/// Exposed over HTTP in Prometheus text exposition format.
+pub struct Metrics {
+    state_gauges: RwLock<HashMap<String, Arc<AtomicI64>>>,
+    state_histograms: RwLock<HashMap<String, Arc<Histogram>>>,
+    poll_counters: RwLock<HashMap<WaitReason, Arc<AtomicU64>>>,
+}
+
+/// RAII handle returned by `Metrics::enter_state`: decrements the gauge and
+/// records the time spent in that state automatically when dropped, no
+/// matter which exit path the caller took (success, quarantine, error, ...),
+/// so the gauge can never leak from a missed decrement on some branch.
+pub struct StateGuard {
+    metrics: Arc<Metrics>,
+    state: String,
+    started: Instant,
+}
+
+impl Drop for StateGuard {
+    fn drop(&mut self) {
+        self.metrics.leave_state(&self.state, self.started.elapsed());
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Metrics {
+            state_gauges: RwLock::new(HashMap::new()),
+            state_histograms: RwLock::new(HashMap::new()),
+            poll_counters: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Marks a sector as having entered `state`, bumping its gauge. Returns
+    /// a guard that decrements the gauge and records the time-in-state
+    /// histogram observation once it's dropped.
+    pub fn enter_state(self: &Arc<Self>, state: &str) -> StateGuard {
+        self.gauge_for(state).fetch_add(1, Ordering::Relaxed);
+        StateGuard {
+            metrics: self.clone(),
+            state: state.to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    fn leave_state(&self, state: &str, elapsed: Duration) {
+        self.gauge_for(state).fetch_sub(1, Ordering::Relaxed);
+        self.histogram_for(state).observe(elapsed);
+    }
+
+    /// Counts one RPC poll issued for `reason`.
+    pub fn incr_poll(&self, reason: WaitReason) {
+        if let Some(counter) = self.poll_counters.read().expect("poll_counters lock poisoned").get(&reason) {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        self.poll_counters
+            .write()
+            .expect("poll_counters lock poisoned")
+            .entry(reason)
+            .or_insert_with(|| Arc::new(AtomicU64::new(0)))
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn gauge_for(&self, state: &str) -> Arc<AtomicI64> {
+        if let Some(gauge) = self.state_gauges.read().expect("state_gauges lock poisoned").get(state) {
+            return gauge.clone();
+        }
+
+        self.state_gauges
+            .write()
+            .expect("state_gauges lock poisoned")
+            .entry(state.to_string())
+            .or_insert_with(|| Arc::new(AtomicI64::new(0)))
+            .clone()
+    }
+
+    fn histogram_for(&self, state: &str) -> Arc<Histogram> {
+        if let Some(hist) = self.state_histograms.read().expect("state_histograms lock poisoned").get(state) {
+            return hist.clone();
+        }
+
+        self.state_histograms
+            .write()
+            .expect("state_histograms lock poisoned")
+            .entry(state.to_string())
+            .or_insert_with(|| Arc::new(Histogram::new()))
+            .clone()
+    }
+
+    /// Renders the current state in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP damocles_worker_sectors_in_state Number of sectors currently in a given sealing state.");
+        let _ = writeln!(out, "# TYPE damocles_worker_sectors_in_state gauge");
+        for (state, gauge) in self.state_gauges.read().expect("state_gauges lock poisoned").iter() {
+            let _ = writeln!(out, "damocles_worker_sectors_in_state{{state=\"{}\"}} {}", state, gauge.load(Ordering::Relaxed));
+        }
+
+        let _ = writeln!(out, "# HELP damocles_worker_state_duration_seconds Time sectors spent in a given sealing state.");
+        let _ = writeln!(out, "# TYPE damocles_worker_state_duration_seconds histogram");
+        for (state, hist) in self.state_histograms.read().expect("state_histograms lock poisoned").iter() {
+            hist.render("damocles_worker_state_duration_seconds", state, &mut out);
+        }
+
+        let _ = writeln!(out, "# HELP damocles_worker_rpc_polls_total RPC polls issued, by the reason the sector was waiting.");
+        let _ = writeln!(out, "# TYPE damocles_worker_rpc_polls_total counter");
+        for (reason, counter) in self.poll_counters.read().expect("poll_counters lock poisoned").iter() {
+            let _ = writeln!(
+                out,
+                "damocles_worker_rpc_polls_total{{reason=\"{}\"}} {}",
+                reason.as_str(),
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        out
+    }
+
+    /// Serves this registry's `render()` output over plain HTTP at `/metrics`
+    /// on `addr`, in a dedicated background thread, for the life of the
+    /// process. No external HTTP framework is pulled in for this -- the
+    /// exposition format is simple text and the request handling needed is
+    /// minimal.
+    pub fn serve(self: Arc<Self>, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr).with_context(|| format!("bind metrics listener on {}", addr))?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let metrics = self.clone();
+                match stream {
+                    Ok(stream) => {
+                        thread::spawn(move || {
+                            if let Err(e) = handle_request(stream, &metrics) {
+                                tracing::warn!(err = %e, "failed to serve a metrics request");
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!(err = %e, "failed to accept metrics connection"),
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn handle_request(mut stream: std::net::TcpStream, metrics: &Metrics) -> Result<()> {
+    // We only ever serve one fixed resource, so there's no need to parse
+    // the request line/headers beyond draining enough to be a polite HTTP
+    // server; any request at all gets the current metrics snapshot back.
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf);
+
+    let body = metrics.render();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).context("write metrics response")
+}
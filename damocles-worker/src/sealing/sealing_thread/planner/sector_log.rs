@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use tracing::field::Field;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+struct SectorLogState {
+    sector_id: String,
+    file: Mutex<std::fs::File>,
+    warnings: AtomicU32,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<SectorLogState>>> = RefCell::new(None);
+}
+
+/// RAII handle established when a sealing job starts driving a particular
+/// sector: while held, every event traced on this thread is also appended
+/// to `<log_dir>/<sector_id>.log`, and warnings (or worse) are counted so
+/// the caller can surface how many were raised once the sector is done.
+pub struct SectorLogGuard {
+    previous: Option<Arc<SectorLogState>>,
+    state: Arc<SectorLogState>,
+}
+
+impl SectorLogGuard {
+    /// Clears the context for this thread and returns how many
+    /// warning-or-above events were emitted while it was active.
+    pub fn finish(self) -> u32 {
+        self.state.warnings.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for SectorLogGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|cell| *cell.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Establishes the per-sector logging context for the current thread. Any
+/// previously active context (e.g. a different sector on a reused worker
+/// thread) is restored once the returned guard is dropped.
+pub fn enter_sector_log(sector_id: String, log_dir: &Path) -> Result<SectorLogGuard> {
+    std::fs::create_dir_all(log_dir).with_context(|| format!("create sector log dir {}", log_dir.display()))?;
+    let path = log_dir.join(format!("{}.log", sector_id));
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("open sector log file {}", path.display()))?;
+
+    let state = Arc::new(SectorLogState {
+        sector_id,
+        file: Mutex::new(file),
+        warnings: AtomicU32::new(0),
+    });
+
+    let previous = CURRENT.with(|cell| cell.borrow_mut().replace(state.clone()));
+
+    Ok(SectorLogGuard { previous, state })
+}
+
+struct MessageVisitor(String);
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// A `tracing_subscriber` layer that, on whichever thread currently has a
+/// `SectorLogGuard` active, also appends the event to that sector's own log
+/// file. Install this alongside whatever layer already writes to
+/// stdout/syslog -- it only adds the per-sector fan-out on top, so stages
+/// don't need a logger handle threaded through every `Event` handler.
+pub struct SectorLogLayer;
+
+impl<S: Subscriber> Layer<S> for SectorLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        CURRENT.with(|cell| {
+            let state = match cell.borrow().as_ref() {
+                Some(state) => state.clone(),
+                None => return,
+            };
+
+            let mut visitor = MessageVisitor(String::new());
+            event.record(&mut visitor);
+
+            if matches!(*event.metadata().level(), tracing::Level::WARN | tracing::Level::ERROR) {
+                state.warnings.fetch_add(1, Ordering::Relaxed);
+            }
+
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            if let Ok(mut file) = state.file.lock() {
+                let _ = writeln!(file, "{} {} {}: {}", now, event.metadata().level(), state.sector_id, visitor.0);
+            }
+        });
+    }
+}
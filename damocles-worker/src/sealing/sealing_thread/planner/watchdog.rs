@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+
+/// One stalled sector a `Watchdog` scan found: which job it belongs to,
+/// which sector/phase is stuck, and how long it's been there.
+#[derive(Debug, Clone)]
+pub struct Stall {
+    pub job_id: String,
+    pub index: usize,
+    pub phase_desc: String,
+    pub stalled_for: Duration,
+}
+
+/// Something a `Watchdog` can scan for stalled sectors and act on. Kept as
+/// a trait, rather than the watchdog reaching directly into `Job`, so one
+/// watchdog can cover every sealing thread in the process without them
+/// sharing a concrete type.
+pub trait StallObserver: Send + Sync {
+    /// Every sector currently polling/waiting whose current phase has run
+    /// longer than `threshold`.
+    fn stalls(&self, threshold: Duration) -> Vec<Stall>;
+
+    /// Record that `stall` was observed: persist it for operator attention
+    /// (or interrupt the job, if the observer is able to safely do so).
+    fn handle_stall(&self, stall: &Stall) -> Result<()>;
+}
+
+/// Background watchdog that periodically scans every registered
+/// `StallObserver` for sectors stuck in polling (e.g. against a wedged
+/// daemon) and flags them, so a spinning proof-check loop doesn't run
+/// forever with nothing noticing.
+pub struct Watchdog {
+    targets: RwLock<Vec<Arc<dyn StallObserver>>>,
+    stall_threshold: Duration,
+    scan_interval: Duration,
+    stopped: AtomicBool,
+}
+
+impl Watchdog {
+    pub fn new(stall_threshold: Duration, scan_interval: Duration) -> Arc<Self> {
+        Arc::new(Watchdog {
+            targets: RwLock::new(Vec::new()),
+            stall_threshold,
+            scan_interval,
+            stopped: AtomicBool::new(false),
+        })
+    }
+
+    /// Registers a sealing job to be included in future scans.
+    pub fn register(&self, target: Arc<dyn StallObserver>) {
+        self.targets.write().expect("watchdog targets lock poisoned").push(target);
+    }
+
+    /// Stops the scan loop after its current (or next) iteration.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Spawns the watchdog's scan loop on a dedicated background thread.
+    /// Meant to be called once at process startup; runs for the life of
+    /// the process, or until `stop` is called.
+    pub fn spawn(self: Arc<Self>) {
+        thread::spawn(move || {
+            while !self.stopped.load(Ordering::Relaxed) {
+                // A panicking scan must not take the watchdog down with it:
+                // log it and re-arm after a short delay instead of dying.
+                if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.scan_once())).is_err() {
+                    tracing::error!("sector watchdog scan panicked, re-arming after a short delay");
+                    thread::sleep(Duration::from_secs(5));
+                    continue;
+                }
+
+                thread::sleep(self.scan_interval);
+            }
+        });
+    }
+
+    fn scan_once(&self) {
+        let targets = self.targets.read().expect("watchdog targets lock poisoned").clone();
+
+        for target in targets {
+            for stall in target.stalls(self.stall_threshold) {
+                tracing::error!(
+                    job = %stall.job_id,
+                    index = stall.index,
+                    phase = %stall.phase_desc,
+                    stalled_for = ?stall.stalled_for,
+                    "sector watchdog: sector has been stuck longer than the stall threshold",
+                );
+
+                if let Err(e) = target.handle_stall(&stall) {
+                    tracing::warn!(job = %stall.job_id, index = stall.index, err = %e, "sector watchdog failed to record a stalled sector, will retry next scan");
+                }
+            }
+        }
+    }
+}
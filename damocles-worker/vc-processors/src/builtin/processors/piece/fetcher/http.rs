@@ -1,12 +1,20 @@
-use std::{env, time::Duration};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+use std::{env, fmt};
 use std::error::Error;
-use serde_urlencoded;
+
 use anyhow::{anyhow, Context};
 use lazy_static::lazy_static;
 use reqwest::{
     blocking::{Client, ClientBuilder, Response},
-    header, redirect, IntoUrl,
+    header, redirect, IntoUrl, Url,
 };
+use sha2::{Digest, Sha256};
 
 use super::PieceFetcher;
 
@@ -20,184 +28,1039 @@ lazy_static! {
         PieceHttpFetcher::from_env().unwrap();
 }
 
+const FETCH_FILE_PATH: &str = "/api/file_opt/fetch";
+
+/// Exponential backoff (with jitter) applied between retried piece-download
+/// attempts, both for the initial request and for resumed requests that
+/// follow a mid-stream I/O error.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    const ENV_KEY_MAX_ATTEMPTS: &'static str = "PIECE_FETCHER_RETRY_MAX_ATTEMPTS";
+    const ENV_KEY_BASE_DELAY_MS: &'static str = "PIECE_FETCHER_RETRY_BASE_DELAY_MS";
+    const ENV_KEY_MAX_DELAY_MS: &'static str = "PIECE_FETCHER_RETRY_MAX_DELAY_MS";
+
+    fn from_env() -> Self {
+        let mut policy = Self::default();
+
+        if let Ok(v) = env::var(Self::ENV_KEY_MAX_ATTEMPTS) {
+            if let Ok(n) = v.parse() {
+                policy.max_attempts = n;
+            }
+        }
+
+        if let Ok(v) = env::var(Self::ENV_KEY_BASE_DELAY_MS) {
+            if let Ok(ms) = v.parse() {
+                policy.base_delay = Duration::from_millis(ms);
+            }
+        }
+
+        if let Ok(v) = env::var(Self::ENV_KEY_MAX_DELAY_MS) {
+            if let Ok(ms) = v.parse() {
+                policy.max_delay = Duration::from_millis(ms);
+            }
+        }
+
+        policy
+    }
+
+    /// Computes the delay to wait before retry number `attempt` (0-indexed),
+    /// doubling the base delay each time and capping at `max_delay`, then
+    /// adding up to 50% jitter so that many concurrent pieces don't retry in
+    /// lockstep.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = 1u32.checked_shl(attempt.min(16)).unwrap_or(u32::MAX);
+        let capped = self
+            .base_delay
+            .checked_mul(exp)
+            .map(|d| d.min(self.max_delay))
+            .unwrap_or(self.max_delay);
+
+        let jitter_cap = (capped.as_millis() as u64 / 2).max(1);
+        let jitter_ms = jitter_seed() % jitter_cap;
+        capped + Duration::from_millis(jitter_ms)
+    }
+}
+
+/// A cheap, dependency-free jitter source: we don't want to pull in `rand`
+/// just to de-synchronize retries, so we mix the current time with the
+/// thread id.
+fn jitter_seed() -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    std::time::Instant::now().hash(&mut hasher);
+    thread::current().id().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A per-process-unique suffix for in-flight temp files, so two concurrent
+/// fetches of the same cache entry (e.g. the same piece URL retried or
+/// requested for two sectors at once) never share a temp file and race on
+/// the rename into place.
+fn tmp_suffix() -> String {
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let seq = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{}-{}-{}", std::process::id(), jitter_seed(), seq)
+}
+
+/// A per-host registry of bearer tokens, so that different piece providers
+/// (multiple markets, gateways, mirrors) can carry different credentials.
+/// Looked up by the request URL's host at request time, including after a
+/// redirect to a different host, rather than blindly forwarding one global
+/// token everywhere.
+struct AuthTokens {
+    by_host: RwLock<HashMap<String, String>>,
+    default: Option<String>,
+}
+
+impl AuthTokens {
+    const ENV_KEY: &'static str = "PIECE_FETCHER_TOKENS";
+
+    /// Parses `host=token` entries (also accepting `host:bearer=token`, an
+    /// alternate spelling for the same bearer credential) separated by
+    /// commas or newlines, on top of the single legacy `default_token` that
+    /// applies to any host without a more specific entry.
+    fn from_env(default_token: Option<String>) -> Self {
+        let mut by_host = HashMap::new();
+
+        if let Ok(raw) = env::var(Self::ENV_KEY) {
+            for entry in raw.split(|c| c == ',' || c == '\n') {
+                let entry = entry.trim();
+                if entry.is_empty() {
+                    continue;
+                }
+
+                match entry.split_once('=') {
+                    Some((host, token)) => {
+                        let host = host.trim().trim_end_matches(":bearer").to_string();
+                        by_host.insert(host, token.trim().to_string());
+                    }
+                    None => tracing::warn!(entry, "ignoring malformed {} entry", Self::ENV_KEY),
+                }
+            }
+        }
+
+        Self {
+            by_host: RwLock::new(by_host),
+            default: default_token,
+        }
+    }
+
+    fn lookup(&self, host: &str) -> Option<String> {
+        self.by_host
+            .read()
+            .ok()
+            .and_then(|tokens| tokens.get(host).cloned())
+            .or_else(|| self.default.clone())
+    }
+
+    fn set(&self, host: String, token: String) {
+        if let Ok(mut tokens) = self.by_host.write() {
+            tokens.insert(host, token);
+        }
+    }
+
+    fn clear(&self, host: &str) {
+        if let Ok(mut tokens) = self.by_host.write() {
+            tokens.remove(host);
+        }
+    }
+}
+
+/// TLS trust configuration for the reqwest clients, letting deployments
+/// behind private PKI (self-signed or internal-CA market/object-store
+/// endpoints) be reached without disabling verification globally.
+#[derive(Default, Clone)]
+pub struct TlsConfig {
+    /// Additional PEM CA bundles to trust, on top of the platform roots.
+    pub ca_cert_paths: Vec<PathBuf>,
+    /// Force the rustls backend instead of the platform-native TLS stack.
+    pub use_rustls: bool,
+    /// Danger: skip certificate validation entirely. Only for test clusters.
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    const ENV_KEY_CA_CERTS: &'static str = "PIECE_FETCHER_CA_CERTS";
+    const ENV_KEY_USE_RUSTLS: &'static str = "PIECE_FETCHER_USE_RUSTLS";
+    const ENV_KEY_DANGER_ACCEPT_INVALID_CERTS: &'static str = "PIECE_FETCHER_DANGER_ACCEPT_INVALID_CERTS";
+
+    fn from_env() -> Self {
+        let ca_cert_paths = env::var(Self::ENV_KEY_CA_CERTS)
+            .ok()
+            .map(|raw| raw.split(',').map(str::trim).filter(|s| !s.is_empty()).map(PathBuf::from).collect())
+            .unwrap_or_default();
+
+        Self {
+            ca_cert_paths,
+            use_rustls: env_flag(Self::ENV_KEY_USE_RUSTLS),
+            danger_accept_invalid_certs: env_flag(Self::ENV_KEY_DANGER_ACCEPT_INVALID_CERTS),
+        }
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    matches!(env::var(key).ok().as_deref(), Some("1") | Some("true") | Some("TRUE") | Some("yes"))
+}
+
 /// A piece fetcher for the http file
 pub struct PieceHttpFetcher {
     client: Client,
     redirect_client: Client,
-    token: Option<String>,
+    auth_tokens: AuthTokens,
+    retry_policy: RetryPolicy,
+    redirect_limit: u32,
+    cache: PieceCache,
 }
 
 impl<U: IntoUrl> PieceFetcher<U> for PieceHttpFetcher {
     type Err = anyhow::Error;
-    type Read = Response;
+    type Read = PieceBody<'static>;
 
     fn open(&self, u: U) -> Result<Self::Read, Self::Err> {
+        let remote_file_url = u.as_str().to_string();
+        let (host, file) = get_host_and_file(&remote_file_url)
+            .map_err(|e| anyhow!("parse remote file url {}: {}", remote_file_url, e))?;
+        let integrity = get_piece_integrity(&remote_file_url);
 
+        let base_url = Url::parse(&host).with_context(|| format!("parse host url {}", host))?;
+        let fetch_url = build_fetch_url(&base_url, &file)?;
 
-        let remote_file_url = u.as_str();
-        match get_host_and_file(remote_file_url) {
-            Ok((host, file)) => {
-                
-                FetchFileUrl = "/api/file_opt/fetch";
-
-                let fetch_url = Url::parse(&host)
-                    .map_err(|e| {
-                        error!("[BH] parse url failed url: {}, err: {}", self.host, e);
-                        e
-                    })?;
-        
-                // 加载 FetchFileUrl（假设它是一个相对 URL 路径）
-                let fetch_url = fetch_url.join(&FetchFileUrl).map_err(|e| {
-                    error!("[BH] parse url failed url: {}, err: {}", "fetchFileUrl", e);
-                    e
-                })?;
-
-                // 创建 URL 查询参数
-                let mut params = vec![
-                    ("file", file.clone()),
-                    ("offer_confirmation", &"true"),
-                ];
-
-                // 将查询参数添加到 URL 中
-                let fetch_url = fetch_url.join(&format!("?{}", serde_urlencoded::to_string(&params)?))
-                    .map_err(|e| {
-                        error!("[BH] add query params failed url: {}, err: {}", fetch_url, e);
-                        e
-                    })?;
-
-                // 创建一个 HTTP 客户端
-                let client = Client::builder()
-                    .timeout(Duration::from_secs(24 * 3600))
-                    .build()?;
-
-                // 创建 HTTP 请求
-                let request = client.request(Method::GET, fetch_url.clone())
-                    .header("Connection", "close")
-                    .build()
-                    .map_err(|e| {
-                        error!("[BH] NewRequest failed err: {}", e);
-                        e
-                    })?;
-
-                // 发送请求并获取响应
-                let resp = client.execute(request).await.map_err(|e| {
-                    error!("[BH] request http failed err: {}", e);
-                    e
-                })?;
-
-                if !resp.status().is_success(){
-                    let status = resp.status();
-                    let fetch_url = fetch_url.clone();
-                    let body = resp.text().await.unwrap_or_else(|_| "Error reading body".to_string());
-                    let err_msg = format!(
-                        "[BH] {}:{} access: {}, body: {}",
-                        status,
-                        status.as_u16(),
-                        fetch_url,
-                        body
-                    );
-                    error!("{}", err_msg);
-                    return Err(anyhow!(
-                        "get resource {} failed invalid status code {}",
-                        resp.url(),
-                        status
-                    ));
+        if let Some(meta) = self.cache.load_meta(&fetch_url) {
+            if !meta.is_empty() {
+                if let Some(body) = self.try_conditional(&fetch_url, &meta, &integrity)? {
+                    return Ok(body);
                 }
-
-                Ok(resp)
-
-            }
-            Err(e) => {
-                return Err(anyhow!(
-                    "remote url {} failed",
-                    remote_file_url,
-                ));
             }
         }
 
-        // let u = u.into_url()?;
-        // let mut resp = self
-        //     .client
-        //     .get(u.clone())
-        //     .send()
-        //     .context("request piece url")?;
-
-        // let mut status_code = resp.status();
-        // if status_code.is_redirection() {
-        //     let redirect_url = resp
-        //         .headers()
-        //         .get(header::LOCATION)
-        //         .context("redirect location not found")
-        //         .and_then(|val| {
-        //             val.to_str().context("convert redirect location to str")
-        //         })
-        //         .and_then(|location| {
-        //             u.join(location).context("join redirect url")
-        //         })?;
-
-        //     let mut req = self.redirect_client.get(redirect_url);
-        //     if let Some(token) = self.token.as_ref() {
-        //         req = req
-        //             .header(
-        //                 header::AUTHORIZATION,
-        //                 format!(
-        //                     "{} {}",
-        //                     Self::HEADER_AUTHORIZATION_BEARER_PREFIX,
-        //                     token
-        //                 ),
-        //             )
-        //             .header("X-VENUS-API-NAMESPACE", "v1.IMarket")
-        //     };
-        //     resp = req.send().context("request to redirected location")?;
-        //     status_code = resp.status();
-        // }
-
-        // if !status_code.is_success() {
-        //     return Err(anyhow!(
-        //         "get resource {} failed invalid status code {}",
-        //         resp.url(),
-        //         status_code
-        //     ));
-        // }
-
-        // Ok(resp)
+        let (final_url, resp) = self.fetch_with_retry(fetch_url)?;
+        Ok(self.wrap_response(final_url, resp, &integrity))
     }
 }
 
 impl PieceHttpFetcher {
     pub const HEADER_AUTHORIZATION_BEARER_PREFIX: &'static str = "Bearer";
     pub const ENV_KEY_PIECE_FETCHER_TOKEN: &'static str = "PIECE_FETCHER_TOKEN";
+    pub const ENV_KEY_REDIRECT_LIMIT: &'static str = "PIECE_FETCHER_REDIRECT_LIMIT";
+    pub const DEFAULT_REDIRECT_LIMIT: u32 = 5;
+    const HEADER_VENUS_API_NAMESPACE: &'static str = "X-VENUS-API-NAMESPACE";
 
     fn from_env() -> anyhow::Result<Self> {
         let token = env::var(Self::ENV_KEY_PIECE_FETCHER_TOKEN).ok();
-        Self::new(token)
+        let redirect_limit = env::var(Self::ENV_KEY_REDIRECT_LIMIT)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::DEFAULT_REDIRECT_LIMIT);
+        Self::new(token, redirect_limit, TlsConfig::from_env())
     }
 
-    fn new(token: Option<String>) -> anyhow::Result<Self> {
-        fn build_http_client(
-            policy: redirect::Policy,
-        ) -> reqwest::Result<Client> {
-            ClientBuilder::new()
+    fn new(token: Option<String>, redirect_limit: u32, tls: TlsConfig) -> anyhow::Result<Self> {
+        fn build_http_client(policy: redirect::Policy, tls: &TlsConfig) -> anyhow::Result<Client> {
+            let mut builder = ClientBuilder::new()
                 .redirect(policy) // handle redirect ourselves
                 .tcp_keepalive(Duration::from_secs(120))
                 .connect_timeout(Duration::from_secs(5))
                 .connection_verbose(true)
-                .pool_max_idle_per_host(10)
-                .build()
+                .pool_max_idle_per_host(10);
+
+            if tls.use_rustls {
+                builder = builder.use_rustls_tls();
+            }
+
+            if tls.danger_accept_invalid_certs {
+                tracing::warn!("accepting invalid TLS certificates for piece fetches; only use this against test clusters");
+                builder = builder.danger_accept_invalid_certs(true);
+            }
+
+            for path in &tls.ca_cert_paths {
+                let pem = fs::read(path).with_context(|| format!("read CA cert {}", path.display()))?;
+                let cert = reqwest::Certificate::from_pem(&pem).with_context(|| format!("parse CA cert {}", path.display()))?;
+                builder = builder.add_root_certificate(cert);
+            }
+
+            builder.build().context("build reqwest client")
         }
 
-        let client = build_http_client(redirect::Policy::none())
+        let client = build_http_client(redirect::Policy::none(), &tls)
             .context("build http client")?;
-        let redirect_client = build_http_client(redirect::Policy::default())
+        // `redirect_client` must not auto-follow either: `follow_redirects`
+        // only ever sees the response handed back to it, so if reqwest chased
+        // further hops on its own first, `redirect_limit` would stop being
+        // enforced past the first hop and `resolve_redirect_url` would never
+        // run for hop 2+.
+        let redirect_client = build_http_client(redirect::Policy::none(), &tls)
             .context("build redirect http client")?;
         Ok(Self {
             client,
             redirect_client,
-            token,
+            auth_tokens: AuthTokens::from_env(token),
+            retry_policy: RetryPolicy::from_env(),
+            redirect_limit,
+            cache: PieceCache::from_env(),
+        })
+    }
+
+    /// Registers (or overrides) the bearer token used for requests to `host`.
+    pub fn set_token(&self, host: impl Into<String>, token: impl Into<String>) {
+        self.auth_tokens.set(host.into(), token.into());
+    }
+
+    /// Removes a previously-registered per-host token, falling back to the
+    /// default token (if any) for subsequent requests to `host`.
+    pub fn clear_token(&self, host: &str) {
+        self.auth_tokens.clear(host);
+    }
+
+    fn bearer_header_for(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+        self.auth_tokens
+            .lookup(host)
+            .map(|token| format!("{} {}", Self::HEADER_AUTHORIZATION_BEARER_PREFIX, token))
+    }
+
+    fn get(&self, url: &Url) -> reqwest::Result<Response> {
+        let mut req = self.client.request(reqwest::Method::GET, url.clone()).header("Connection", "close");
+        if let Some(bearer) = self.bearer_header_for(url) {
+            req = req.header(header::AUTHORIZATION, bearer);
+        }
+        req.send()
+    }
+
+    fn range_request(&self, url: &Url, offset: u64) -> reqwest::Result<Response> {
+        let mut req = self
+            .client
+            .request(reqwest::Method::GET, url.clone())
+            .header("Connection", "close")
+            .header(header::RANGE, format!("bytes={}-", offset));
+        if let Some(bearer) = self.bearer_header_for(url) {
+            req = req.header(header::AUTHORIZATION, bearer);
+        }
+        req.send()
+    }
+
+    /// Re-issues a request at `url` through `redirect_client` for one redirect
+    /// hop, re-attaching the market-service namespace header and the bearer
+    /// token for `url`'s host, re-evaluated in case the redirect crossed
+    /// hosts. Since `redirect_client` does not auto-follow (see `new`), this
+    /// is the one place every hop's headers get (re)computed, including hop 2+.
+    fn get_redirected(&self, url: &Url) -> reqwest::Result<Response> {
+        let mut req = self
+            .redirect_client
+            .request(reqwest::Method::GET, url.clone())
+            .header("Connection", "close")
+            .header(Self::HEADER_VENUS_API_NAMESPACE, "v1.IMarket");
+        match self.bearer_header_for(url) {
+            Some(bearer) => req = req.header(header::AUTHORIZATION, bearer),
+            None => tracing::debug!(host = ?url.host_str(), "no bearer token registered for this redirect hop's host"),
+        }
+        req.send()
+    }
+
+    /// Follows a chain of 3xx responses starting from `resp`, re-resolving
+    /// each `Location` header against the URL it was received from and
+    /// re-issuing through the redirect client, up to `redirect_limit` hops.
+    fn follow_redirects(&self, mut current_url: Url, mut resp: Response) -> anyhow::Result<(Url, Response)> {
+        let mut hops = 0u32;
+        while resp.status().is_redirection() {
+            if hops >= self.redirect_limit {
+                return Err(anyhow!("too many redirects ({}) while fetching {}", self.redirect_limit, current_url));
+            }
+
+            let location = resp
+                .headers()
+                .get(header::LOCATION)
+                .context("redirect response missing Location header")?
+                .to_str()
+                .context("redirect Location header is not valid utf-8")?;
+
+            let next_url = resolve_redirect_url(&current_url, location)?;
+
+            resp = self.get_redirected(&next_url).with_context(|| format!("follow redirect to {}", next_url))?;
+            current_url = next_url;
+            hops += 1;
+        }
+
+        Ok((current_url, resp))
+    }
+
+    /// Issues the initial request for `url`, retrying connection failures
+    /// and 5xx responses with exponential backoff before giving up.
+    fn fetch_with_retry(&self, url: Url) -> anyhow::Result<(Url, Response)> {
+        let mut attempt = 0u32;
+        loop {
+            let outcome = match self.get(&url) {
+                Ok(resp) => self.follow_redirects(url.clone(), resp),
+                Err(e) => Err(anyhow!(e)),
+            };
+            match outcome {
+                Ok((final_url, resp)) => {
+                    if resp.status().is_success() {
+                        return Ok((final_url, resp));
+                    } else if resp.status().is_server_error() {
+                        let status = resp.status();
+                        attempt = self.retry_or_fail(attempt, &final_url, anyhow!("server error: {}", status))?;
+                    } else {
+                        let status = resp.status();
+                        return Err(anyhow!("get resource {} failed invalid status code {}", final_url, status));
+                    }
+                }
+                Err(e) => {
+                    attempt = self.retry_or_fail(attempt, &url, e)?;
+                }
+            }
+        }
+    }
+
+    /// Sends a conditional GET carrying the cached validator. Returns
+    /// `Some(..)` serving the cached copy on a `304`, `Some(..)` wrapping a
+    /// fresh body if the server answered with a `200`, or `None` if the
+    /// conditional request itself failed and a plain retried fetch should be
+    /// attempted instead.
+    fn try_conditional(&self, url: &Url, meta: &CacheMeta, integrity: &PieceIntegrity) -> anyhow::Result<Option<PieceBody<'static>>> {
+        let resp = match self.conditional_get(url, meta) {
+            Ok(resp) => resp,
+            Err(e) => {
+                tracing::warn!(err = %e, %url, "conditional fetch failed, falling back to a full fetch");
+                return Ok(None);
+            }
+        };
+
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            tracing::debug!(%url, "piece unchanged since last fetch, serving cached copy");
+            return Ok(match self.cache.open_body(url) {
+                Ok(file) => Some(PieceBody {
+                    source: PieceSource::Cached(file),
+                    integrity: IntegrityCheck::new(integrity.clone()),
+                }),
+                Err(e) => {
+                    tracing::warn!(err = %e, %url, "cached piece body missing after a 304, falling back to a full fetch");
+                    None
+                }
+            });
+        }
+
+        if resp.status().is_success() {
+            return Ok(Some(self.wrap_response(url.clone(), resp, integrity)));
+        }
+
+        Ok(None)
+    }
+
+    fn conditional_get(&self, url: &Url, meta: &CacheMeta) -> reqwest::Result<Response> {
+        let mut req = self.client.request(reqwest::Method::GET, url.clone()).header("Connection", "close");
+        if let Some(bearer) = self.bearer_header_for(url) {
+            req = req.header(header::AUTHORIZATION, bearer);
+        }
+        if let Some(etag) = &meta.etag {
+            req = req.header(header::IF_NONE_MATCH, etag);
+        } else if let Some(last_modified) = &meta.last_modified {
+            req = req.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+        req.send()
+    }
+
+    /// Wraps a successful response body in a `Read` adapter, additionally
+    /// teeing the bytes into the local piece cache when the response carries
+    /// a validator and isn't marked `no-store`/`no-cache`, and verifying the
+    /// declared digest/size (if any) as the body is consumed.
+    fn wrap_response(&self, url: Url, resp: Response, integrity: &PieceIntegrity) -> PieceBody<'static> {
+        let fetcher = fetcher_ref();
+        let meta = if is_cacheable(&resp) { CacheMeta::from_headers(&resp) } else { CacheMeta::default() };
+        let reader = ResumableReader::new(fetcher, url.clone(), resp);
+        let integrity = IntegrityCheck::new(integrity.clone());
+
+        if !meta.is_empty() {
+            if let Some((tmp_path, tmp_file)) = fetcher.cache.prepare_tmp(&url) {
+                return PieceBody {
+                    source: PieceSource::CachingRemote(CachingReader {
+                        inner: reader,
+                        tmp_path,
+                        tmp_file,
+                        url,
+                        meta,
+                        cache: &fetcher.cache,
+                        write_failed: false,
+                        settled: false,
+                    }),
+                    integrity,
+                };
+            }
+        }
+        PieceBody {
+            source: PieceSource::Remote(reader),
+            integrity,
+        }
+    }
+
+    fn retry_or_fail(&self, attempt: u32, url: &Url, err: anyhow::Error) -> anyhow::Result<u32> {
+        if attempt + 1 >= self.retry_policy.max_attempts {
+            return Err(err.context(format!("exhausted {} attempts fetching {}", self.retry_policy.max_attempts, url)));
+        }
+        let delay = self.retry_policy.backoff(attempt);
+        tracing::warn!(attempt, ?delay, err = %err, %url, "piece fetch failed, retrying");
+        thread::sleep(delay);
+        Ok(attempt + 1)
+    }
+}
+
+/// Resolves a `Location` header value against the URL that produced it,
+/// covering the three cases allowed by RFC 3986: absolute (`http(s)://...`),
+/// protocol-relative (`//host/path`), and relative (joined onto `base`).
+fn resolve_redirect_url(base: &Url, location: &str) -> anyhow::Result<Url> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        Url::parse(location).with_context(|| format!("parse absolute redirect location {}", location))
+    } else if let Some(rest) = location.strip_prefix("//") {
+        let absolute = format!("{}://{}", base.scheme(), rest);
+        Url::parse(&absolute).with_context(|| format!("parse protocol-relative redirect location {}", location))
+    } else {
+        base.join(location)
+            .with_context(|| format!("join relative redirect location {} onto {}", location, base))
+    }
+}
+
+fn build_fetch_url(base: &Url, file: &str) -> anyhow::Result<Url> {
+    let mut url = base.join(FETCH_FILE_PATH).with_context(|| format!("join {} onto {}", FETCH_FILE_PATH, base))?;
+    url.query_pairs_mut()
+        .append_pair("file", file)
+        .append_pair("offer_confirmation", "true");
+    Ok(url)
+}
+
+/// A `Read` adapter over a piece download that transparently resumes with a
+/// `Range: bytes=<offset>-` request whenever the underlying stream fails
+/// before the declared `Content-Length` has been delivered in full.
+pub struct ResumableReader<'a> {
+    fetcher: &'a PieceHttpFetcher,
+    url: Url,
+    resp: Response,
+    delivered: u64,
+    total_len: Option<u64>,
+    attempt: u32,
+}
+
+impl<'a> ResumableReader<'a> {
+    fn new(fetcher: &'a PieceHttpFetcher, url: Url, resp: Response) -> Self {
+        let total_len = resp.content_length();
+        Self {
+            fetcher,
+            url,
+            resp,
+            delivered: 0,
+            total_len,
+            attempt: 0,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        matches!(self.total_len, Some(total) if self.delivered >= total)
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        loop {
+            if self.attempt + 1 >= self.fetcher.retry_policy.max_attempts {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("exhausted {} attempts resuming {} at offset {}", self.fetcher.retry_policy.max_attempts, self.url, self.delivered),
+                ));
+            }
+
+            let delay = self.fetcher.retry_policy.backoff(self.attempt);
+            self.attempt += 1;
+            thread::sleep(delay);
+
+            match self.fetcher.range_request(&self.url, self.delivered) {
+                Ok(resp) if resp.status() == reqwest::StatusCode::PARTIAL_CONTENT && content_range_start_matches(&resp, self.delivered) => {
+                    self.resp = resp;
+                    self.attempt = 0;
+                    return Ok(());
+                }
+                Ok(resp) => {
+                    tracing::warn!(
+                        attempt = self.attempt,
+                        offset = self.delivered,
+                        status = %resp.status(),
+                        "resume request did not honor our Range, retrying rather than risk a corrupted/duplicated stream"
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!(attempt = self.attempt, offset = self.delivered, err = %e, "resume request failed, retrying");
+                    continue;
+                }
+            }
+        }
+    }
+}
+
+/// Whether a `206 Partial Content` response's `Content-Range` header starts
+/// exactly at `offset`, i.e. it's safe to keep appending to what we've
+/// already delivered. A server that ignores `Range` and returns `200` with
+/// the full body again is caught by the `206` check in the caller; this
+/// catches the rarer case of a `206` that starts somewhere else entirely.
+fn content_range_start_matches(resp: &Response, offset: u64) -> bool {
+    resp.headers()
+        .get(header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range_start)
+        .is_some_and(|start| start == offset)
+}
+
+/// Parses the start offset out of a `Content-Range: bytes <start>-<end>/<total>`
+/// header value.
+fn parse_content_range_start(value: &str) -> Option<u64> {
+    let rest = value.strip_prefix("bytes ")?;
+    let start = rest.split(['-', '/']).next()?;
+    start.parse().ok()
+}
+
+impl Read for ResumableReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.is_complete() {
+            return Ok(0);
+        }
+
+        loop {
+            match self.resp.read(buf) {
+                Ok(0) => {
+                    if let Some(total) = self.total_len {
+                        if self.delivered < total {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                format!("piece truncated at {} of {} declared bytes", self.delivered, total),
+                            ));
+                        }
+                    }
+                    return Ok(0);
+                }
+                Ok(n) => {
+                    self.delivered += n as u64;
+                    return Ok(n);
+                }
+                Err(e) => {
+                    tracing::warn!(offset = self.delivered, err = %e, "piece download interrupted, resuming");
+                    self.resume()?;
+                }
+            }
+        }
+    }
+}
+
+/// The body handed back from `PieceHttpFetcher::open`: either a live
+/// (possibly resumed) remote stream, the same stream being teed into the
+/// local piece cache as it's consumed, or a cached copy served straight off
+/// disk after a `304 Not Modified`. Incrementally verifies the declared
+/// digest/size (when the `remoteFileUrl` carried one) as bytes are read,
+/// failing at EOF if either doesn't match.
+pub struct PieceBody<'a> {
+    source: PieceSource<'a>,
+    integrity: Option<IntegrityCheck>,
+}
+
+enum PieceSource<'a> {
+    Remote(ResumableReader<'a>),
+    CachingRemote(CachingReader<'a>),
+    Cached(fs::File),
+}
+
+impl Read for PieceSource<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            PieceSource::Remote(r) => r.read(buf),
+            PieceSource::CachingRemote(r) => r.read(buf),
+            PieceSource::Cached(f) => f.read(buf),
+        }
+    }
+}
+
+impl PieceSource<'_> {
+    /// Promotes a caching source's tee'd tmp file into the cache now that
+    /// the body it holds has passed integrity verification; a no-op for
+    /// sources that aren't teeing into the cache.
+    fn commit_cache(&mut self) {
+        if let PieceSource::CachingRemote(r) = self {
+            r.commit();
+        }
+    }
+
+    /// Drops a caching source's tee'd tmp file instead of promoting it,
+    /// because the body it holds failed integrity verification and must
+    /// not be served from the cache on a future fetch.
+    fn discard_cache(&mut self) {
+        if let PieceSource::CachingRemote(r) = self {
+            r.discard();
+        }
+    }
+}
+
+impl Read for PieceBody<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.source.read(buf)?;
+        if n == 0 {
+            // Integrity must pass before any tee'd bytes are promoted into
+            // the cache -- otherwise a corrupt-but-right-length body gets
+            // persisted as the canonical cached copy and keeps being served
+            // from a 304 on every later fetch of the same URL.
+            if let Some(check) = self.integrity.take() {
+                if let Err(e) = check.finish() {
+                    self.source.discard_cache();
+                    return Err(e);
+                }
+            }
+            self.source.commit_cache();
+            return Ok(0);
+        }
+
+        if let Some(check) = &mut self.integrity {
+            check.observe(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+/// Expected content digest and length for a piece, parsed from optional
+/// `sha256=<hex>` / `size=<bytes>` segments appended to the `remoteFileUrl`
+/// token after the required `host|file` pair.
+#[derive(Debug, Clone, Default)]
+pub struct PieceIntegrity {
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl PieceIntegrity {
+    fn is_empty(&self) -> bool {
+        self.sha256.is_none() && self.size.is_none()
+    }
+
+    fn parse(parts: &[&str]) -> Self {
+        let mut integrity = Self::default();
+        for part in parts {
+            if let Some(v) = part.strip_prefix("sha256=") {
+                integrity.sha256 = Some(v.to_ascii_lowercase());
+            } else if let Some(v) = part.strip_prefix("size=") {
+                integrity.size = v.parse().ok();
+            }
+        }
+        integrity
+    }
+}
+
+/// Parses the optional `sha256=...`/`size=...` segments that may follow the
+/// `host|file` pair in a `remoteFileUrl` token.
+pub fn get_piece_integrity(remote_file_url: &str) -> PieceIntegrity {
+    let parts: Vec<&str> = remote_file_url.split('|').collect();
+    if parts.len() > 2 {
+        PieceIntegrity::parse(&parts[2..])
+    } else {
+        PieceIntegrity::default()
+    }
+}
+
+#[derive(Debug)]
+pub enum PieceIntegrityError {
+    DigestMismatch { expected: String, actual: String },
+    SizeMismatch { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for PieceIntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DigestMismatch { expected, actual } => {
+                write!(f, "piece digest mismatch: expected sha256={}, got {}", expected, actual)
+            }
+            Self::SizeMismatch { expected, actual } => {
+                write!(f, "piece size mismatch: expected {} bytes, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl Error for PieceIntegrityError {}
+
+/// Incrementally hashes and counts bytes as a piece is read, checking the
+/// running totals against the declared [`PieceIntegrity`] at EOF.
+struct IntegrityCheck {
+    expected: PieceIntegrity,
+    hasher: Option<Sha256>,
+    delivered: u64,
+}
+
+impl IntegrityCheck {
+    fn new(expected: PieceIntegrity) -> Option<Self> {
+        if expected.is_empty() {
+            return None;
+        }
+        let hasher = expected.sha256.is_some().then(Sha256::new);
+        Some(Self {
+            expected,
+            hasher,
+            delivered: 0,
         })
     }
+
+    fn observe(&mut self, bytes: &[u8]) {
+        self.delivered += bytes.len() as u64;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(bytes);
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        if let Some(expected_size) = self.expected.size {
+            if self.delivered != expected_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    PieceIntegrityError::SizeMismatch {
+                        expected: expected_size,
+                        actual: self.delivered,
+                    },
+                ));
+            }
+        }
+
+        if let (Some(expected), Some(hasher)) = (&self.expected.sha256, self.hasher) {
+            let actual = hex_encode(&hasher.finalize());
+            if &actual != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    PieceIntegrityError::DigestMismatch {
+                        expected: expected.clone(),
+                        actual,
+                    },
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{:02x}", b);
+    }
+    out
+}
+
+/// Wraps a [`ResumableReader`] and mirrors every byte read into a temp file.
+/// The temp file is only promoted into the cache once the caller (see
+/// `PieceBody::read`) has verified the full body against its declared
+/// digest/size and calls [`commit`](CachingReader::commit); a body that
+/// fails that check must instead be [`discard`](CachingReader::discard)ed so
+/// a corrupt response never gets served back out of the cache.
+pub struct CachingReader<'a> {
+    inner: ResumableReader<'a>,
+    tmp_path: PathBuf,
+    tmp_file: fs::File,
+    url: Url,
+    meta: CacheMeta,
+    cache: &'a PieceCache,
+    write_failed: bool,
+    settled: bool,
+}
+
+impl Read for CachingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 {
+            return Ok(0);
+        }
+
+        if !self.write_failed {
+            if let Err(e) = self.tmp_file.write_all(&buf[..n]) {
+                tracing::warn!(err = %e, url = %self.url, "failed to write piece cache tmp file, disabling cache for this fetch");
+                self.write_failed = true;
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+impl CachingReader<'_> {
+    /// Promotes the tee'd tmp file into the cache. Called only after the
+    /// full body has passed integrity verification.
+    fn commit(&mut self) {
+        if self.settled || self.write_failed {
+            return;
+        }
+        self.settled = true;
+
+        let result = self
+            .tmp_file
+            .flush()
+            .map_err(anyhow::Error::from)
+            .and_then(|_| self.cache.store(&self.url, &self.meta, &self.tmp_path));
+
+        if let Err(e) = result {
+            tracing::warn!(err = %e, url = %self.url, "failed to persist piece cache entry");
+        }
+    }
+
+    /// Removes the tee'd tmp file instead of promoting it, because the body
+    /// it holds failed integrity verification.
+    fn discard(&mut self) {
+        if self.settled {
+            return;
+        }
+        self.settled = true;
+
+        if let Err(e) = fs::remove_file(&self.tmp_path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                tracing::warn!(err = %e, path = %self.tmp_path.display(), "failed to remove piece cache tmp file after a failed integrity check");
+            }
+        }
+    }
+}
+
+/// A minimal on-disk, ETag/Last-Modified-keyed cache for fetched piece
+/// bytes. Disabled (a pure pass-through) unless `PIECE_FETCHER_CACHE_DIR` is
+/// set.
+struct PieceCache {
+    dir: Option<PathBuf>,
+}
+
+#[derive(Default, Clone)]
+struct CacheMeta {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheMeta {
+    fn from_headers(resp: &Response) -> Self {
+        Self {
+            etag: resp.headers().get(header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string),
+            last_modified: resp
+                .headers()
+                .get(header::LAST_MODIFIED)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut meta = Self::default();
+        for line in content.lines() {
+            if let Some(v) = line.strip_prefix("etag=") {
+                meta.etag = Some(v.to_string());
+            } else if let Some(v) = line.strip_prefix("last-modified=") {
+                meta.last_modified = Some(v.to_string());
+            }
+        }
+        meta
+    }
+
+    fn serialize(&self) -> String {
+        let mut out = String::new();
+        if let Some(etag) = &self.etag {
+            out.push_str(&format!("etag={}\n", etag));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            out.push_str(&format!("last-modified={}\n", last_modified));
+        }
+        out
+    }
+}
+
+impl PieceCache {
+    const ENV_KEY_CACHE_DIR: &'static str = "PIECE_FETCHER_CACHE_DIR";
+
+    fn from_env() -> Self {
+        Self {
+            dir: env::var(Self::ENV_KEY_CACHE_DIR).ok().map(PathBuf::from),
+        }
+    }
+
+    fn entry_paths(&self, url: &Url) -> Option<(PathBuf, PathBuf)> {
+        let entry_dir = self.dir.as_ref()?.join(cache_key(url));
+        Some((entry_dir.join("body"), entry_dir.join("meta")))
+    }
+
+    fn load_meta(&self, url: &Url) -> Option<CacheMeta> {
+        let (_, meta_path) = self.entry_paths(url)?;
+        fs::read_to_string(meta_path).ok().map(|s| CacheMeta::parse(&s))
+    }
+
+    fn open_body(&self, url: &Url) -> io::Result<fs::File> {
+        let (body_path, _) = self
+            .entry_paths(url)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "piece cache is disabled"))?;
+        fs::File::open(body_path)
+    }
+
+    /// Creates a fresh temp file under the cache entry's directory, ready to
+    /// receive streamed bytes. Returns `None` if caching is disabled or the
+    /// temp file couldn't be created, in which case callers should fall back
+    /// to a plain, non-caching read.
+    fn prepare_tmp(&self, url: &Url) -> Option<(PathBuf, fs::File)> {
+        let (body_path, _) = self.entry_paths(url)?;
+        let entry_dir = body_path.parent()?;
+        if let Err(e) = fs::create_dir_all(entry_dir) {
+            tracing::warn!(err = %e, dir = ?entry_dir, "failed to create piece cache directory, caching disabled for this fetch");
+            return None;
+        }
+        let tmp_path = entry_dir.join(format!("body.tmp.{}", tmp_suffix()));
+        match fs::File::create(&tmp_path) {
+            Ok(f) => Some((tmp_path, f)),
+            Err(e) => {
+                tracing::warn!(err = %e, path = ?tmp_path, "failed to create piece cache tmp file, caching disabled for this fetch");
+                None
+            }
+        }
+    }
+
+    fn store(&self, url: &Url, meta: &CacheMeta, tmp_path: &Path) -> anyhow::Result<()> {
+        let (body_path, meta_path) = self.entry_paths(url).context("piece cache is disabled")?;
+        fs::rename(tmp_path, &body_path).with_context(|| format!("promote {} into piece cache", url))?;
+        fs::write(&meta_path, meta.serialize()).context("write piece cache metadata")?;
+        Ok(())
+    }
 }
 
+fn cache_key(url: &Url) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    url.as_str().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Respects `no-store`/`no-cache` in `Cache-Control` so volatile resources
+/// are never served stale out of the local cache.
+fn is_cacheable(resp: &Response) -> bool {
+    resp.headers()
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            let lower = v.to_ascii_lowercase();
+            !(lower.contains("no-store") || lower.contains("no-cache"))
+        })
+        .unwrap_or(true)
+}
 
 #[derive(Debug)]
 pub struct InvalidRemoteFileUrl {
@@ -214,12 +1077,15 @@ impl Error for InvalidRemoteFileUrl {}
 
 pub fn get_host_and_file(remote_file_url: &str) -> Result<(String, String), Box<dyn Error>> {
     let parts: Vec<&str> = remote_file_url.split('|').collect();
-    
-    if parts.len() != 2 {
+
+    // `host|file` is required; any further `|`-separated segments carry
+    // optional integrity metadata and are parsed separately by
+    // `get_piece_integrity`.
+    if parts.len() < 2 {
         return Err(Box::new(InvalidRemoteFileUrl {
             message: format!("unknown remoteFileUrl: {}", remote_file_url),
         }));
     }
-    
+
     Ok((parts[0].to_string(), parts[1].to_string()))
-}
\ No newline at end of file
+}